@@ -0,0 +1,242 @@
+
+use nalgebra_glm::Vec3;
+use std::f32::consts::PI;
+use crate::camera::Camera;
+
+// Curva que sigue la cámara entre los fotogramas clave
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Interpolation {
+    // Pasa por todos los fotogramas clave, con tangentes tomadas de los vecinos
+    CatmullRom,
+    // Los fotogramas clave son los puntos de control de una sola curva de Bézier: solo pasa
+    // por el primero y el último, y los tiempos de los intermedios no se usan
+    Bezier,
+}
+
+// Reparto del tiempo a lo largo de todo el recorrido
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Easing {
+    Linear,
+    EaseIn,
+    EaseOut,
+    EaseInOut,
+}
+
+impl Easing {
+    // Transforma `t` en 0..1 manteniendo los extremos
+    pub fn apply(&self, t: f32) -> f32 {
+        let t = t.clamp(0.0, 1.0);
+        match self {
+            Easing::Linear => t,
+            Easing::EaseIn => t * t * t,
+            Easing::EaseOut => 1.0 - (1.0 - t).powi(3),
+            Easing::EaseInOut => t * t * (3.0 - 2.0 * t),
+        }
+    }
+}
+
+// Estado de la cámara en un instante `time` (segundos desde el inicio)
+#[derive(Debug, Clone, Copy)]
+pub struct Keyframe {
+    pub time: f32,
+    pub eye: Vec3,
+    pub center: Vec3,
+    pub up: Vec3,
+    pub fov: f32,  // Radianes
+}
+
+impl Keyframe {
+    pub fn new(time: f32, eye: Vec3, center: Vec3, up: Vec3, fov: f32) -> Self {
+        Keyframe { time, eye, center, up, fov }
+    }
+
+    // Combinación lineal de fotogramas clave; la usan las dos interpolaciones
+    fn weighted(keyframes: &[Keyframe], weights: &[f32]) -> Keyframe {
+        let mut result = Keyframe::new(0.0, Vec3::zeros(), Vec3::zeros(), Vec3::zeros(), 0.0);
+        for (keyframe, &weight) in keyframes.iter().zip(weights) {
+            result.eye += keyframe.eye * weight;
+            result.center += keyframe.center * weight;
+            result.up += keyframe.up * weight;
+            result.fov += keyframe.fov * weight;
+        }
+        result
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct CameraPath {
+    pub keyframes: Vec<Keyframe>,  // Ordenados por tiempo
+    pub interpolation: Interpolation,
+    pub easing: Easing,
+    pub closed: bool,  // El último fotograma coincide con el primero y el recorrido se repite sin saltos
+}
+
+impl CameraPath {
+    pub fn new(keyframes: Vec<Keyframe>, interpolation: Interpolation, easing: Easing) -> Self {
+        let mut keyframes = keyframes;
+        keyframes.sort_by(|a, b| a.time.total_cmp(&b.time));
+        CameraPath { keyframes, interpolation, easing, closed: false }
+    }
+
+    // Vuelta completa alrededor de `target` en `duration` segundos, empezando en `eye` y
+    // conservando su altura y distancia al objetivo
+    pub fn turntable(target: Vec3, eye: Vec3, fov: f32, duration: f32) -> Self {
+        let offset = eye - target;
+        let radius = (offset.x * offset.x + offset.z * offset.z).sqrt();
+        let start = offset.z.atan2(offset.x);
+        let steps = 16;
+        let keyframes = (0..=steps)
+            .map(|i| {
+                let fraction = i as f32 / steps as f32;
+                let angle = start + 2.0 * PI * fraction;
+                let position = target + Vec3::new(radius * angle.cos(), offset.y, radius * angle.sin());
+                Keyframe::new(duration * fraction, position, target, Vec3::new(0.0, 1.0, 0.0), fov)
+            })
+            .collect();
+        CameraPath { keyframes, interpolation: Interpolation::CatmullRom, easing: Easing::Linear, closed: true }
+    }
+
+    // Lee un recorrido de texto: una línea por fotograma clave con
+    // `tiempo ojo.x ojo.y ojo.z centro.x centro.y centro.z arriba.x arriba.y arriba.z fov_en_grados`;
+    // las líneas vacías y las que empiezan por `#` se ignoran
+    pub fn load(path: &str, interpolation: Interpolation, easing: Easing) -> Self {
+        let text = std::fs::read_to_string(path).expect("Error al abrir el recorrido de cámara");
+        let keyframes = text
+            .lines()
+            .map(str::trim)
+            .filter(|line| !line.is_empty() && !line.starts_with('#'))
+            .map(|line| {
+                let values: Vec<f32> = line
+                    .split_whitespace()
+                    .map(|value| value.parse().expect("Los fotogramas clave deben contener solo números"))
+                    .collect();
+                assert!(values.len() == 11, "Cada fotograma clave necesita 11 valores: {}", line);
+                let vec = |i: usize| Vec3::new(values[i], values[i + 1], values[i + 2]);
+                Keyframe::new(values[0], vec(1), vec(4), vec(7), values[10].to_radians())
+            })
+            .collect::<Vec<_>>();
+        assert!(!keyframes.is_empty(), "El recorrido de cámara no tiene fotogramas clave");
+        CameraPath::new(keyframes, interpolation, easing)
+    }
+
+    pub fn duration(&self) -> f32 {
+        let first = self.keyframes.first().map_or(0.0, |k| k.time);
+        let last = self.keyframes.last().map_or(0.0, |k| k.time);
+        last - first
+    }
+
+    // Estado interpolado en el instante `time`, recortado al rango del recorrido
+    pub fn sample(&self, time: f32) -> Keyframe {
+        let keys = &self.keyframes;
+        let start = keys[0].time;
+        let duration = self.duration();
+        if keys.len() == 1 || duration <= 0.0 {
+            return keys[0];
+        }
+        let time = start + self.easing.apply((time - start) / duration) * duration;
+
+        match self.interpolation {
+            Interpolation::Bezier => {
+                // De Casteljau sobre todos los fotogramas clave
+                let t = (time - start) / duration;
+                let mut points = keys.clone();
+                for level in (1..points.len()).rev() {
+                    for i in 0..level {
+                        points[i] = Keyframe::weighted(&[points[i], points[i + 1]], &[1.0 - t, t]);
+                    }
+                }
+                Keyframe { time, ..points[0] }
+            }
+            Interpolation::CatmullRom => {
+                let last = keys.len() - 1;
+                let segment = keys.windows(2).position(|pair| time < pair[1].time).unwrap_or(last - 1);
+                let t = (time - keys[segment].time) / (keys[segment + 1].time - keys[segment].time).max(1e-6);
+
+                // En un recorrido cerrado los vecinos de los extremos dan la vuelta
+                // (saltando el último, que repite el primero); si no, se repiten los extremos
+                let previous = match segment {
+                    0 if self.closed => last - 1,
+                    0 => 0,
+                    i => i - 1,
+                };
+                let next = match segment + 2 {
+                    i if i <= last => i,
+                    _ if self.closed => 1,
+                    _ => last,
+                };
+                let (t2, t3) = (t * t, t * t * t);
+                let weights = [
+                    0.5 * (-t3 + 2.0 * t2 - t),
+                    0.5 * (3.0 * t3 - 5.0 * t2 + 2.0),
+                    0.5 * (-3.0 * t3 + 4.0 * t2 + t),
+                    0.5 * (t3 - t2),
+                ];
+                let points = [keys[previous], keys[segment], keys[segment + 1], keys[next]];
+                Keyframe { time, ..Keyframe::weighted(&points, &weights) }
+            }
+        }
+    }
+
+    // Coloca la cámara en el estado del instante `time`
+    pub fn apply(&self, camera: &mut Camera, time: f32) {
+        let keyframe = self.sample(time);
+        camera.eye = keyframe.eye;
+        camera.center = keyframe.center;
+        camera.up = keyframe.up.try_normalize(1e-6).unwrap_or(Vec3::new(0.0, 1.0, 0.0));
+        camera.fov = keyframe.fov;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn keyframes() -> Vec<Keyframe> {
+        let up = Vec3::new(0.0, 1.0, 0.0);
+        vec![
+            Keyframe::new(0.0, Vec3::new(0.0, 1.0, 5.0), Vec3::zeros(), up, 1.0),
+            Keyframe::new(1.5, Vec3::new(3.0, 2.0, 4.0), Vec3::new(0.5, 0.0, 0.0), up, 1.2),
+            Keyframe::new(2.0, Vec3::new(4.0, 1.5, 0.0), Vec3::new(1.0, 0.5, 0.0), up, 0.8),
+            Keyframe::new(4.0, Vec3::new(-2.0, 3.0, -3.0), Vec3::zeros(), up, 1.0),
+        ]
+    }
+
+    #[test]
+    fn catmull_rom_passes_through_keyframes() {
+        let path = CameraPath::new(keyframes(), Interpolation::CatmullRom, Easing::Linear);
+        for keyframe in &path.keyframes {
+            let sample = path.sample(keyframe.time);
+            assert!((sample.eye - keyframe.eye).magnitude() < 1e-5, "Ojo en t = {}", keyframe.time);
+            assert!((sample.center - keyframe.center).magnitude() < 1e-5, "Centro en t = {}", keyframe.time);
+            assert!((sample.fov - keyframe.fov).abs() < 1e-5, "FOV en t = {}", keyframe.time);
+        }
+    }
+
+    #[test]
+    fn easings_keep_the_path_ends() {
+        let keys = keyframes();
+        for easing in [Easing::Linear, Easing::EaseIn, Easing::EaseOut, Easing::EaseInOut] {
+            assert_eq!((easing.apply(0.0), easing.apply(1.0)), (0.0, 1.0));
+            assert!((1..10).all(|i| easing.apply(i as f32 / 10.0) > easing.apply((i - 1) as f32 / 10.0)), "{:?} no es creciente", easing);
+            for interpolation in [Interpolation::CatmullRom, Interpolation::Bezier] {
+                let path = CameraPath::new(keys.clone(), interpolation, easing);
+                assert!((path.sample(0.0).eye - keys[0].eye).magnitude() < 1e-5);
+                assert!((path.sample(4.0).eye - keys[3].eye).magnitude() < 1e-5);
+            }
+        }
+    }
+
+    #[test]
+    fn turntable_is_continuous_where_it_wraps() {
+        let duration = 8.0;
+        let path = CameraPath::turntable(Vec3::new(1.0, 0.0, -2.0), Vec3::new(4.0, 2.0, 2.0), 1.0, duration);
+        let (start, end) = (path.sample(0.0), path.sample(duration));
+        assert!((start.eye - end.eye).magnitude() < 1e-4);
+
+        // La velocidad al llegar al final es la misma que al salir del principio
+        let h = duration * 1e-3;
+        let arriving = (end.eye - path.sample(duration - h).eye) / h;
+        let leaving = (path.sample(h).eye - start.eye) / h;
+        assert!((arriving - leaving).magnitude() < 0.05 * leaving.magnitude(), "{:?} frente a {:?}", arriving, leaving);
+    }
+}
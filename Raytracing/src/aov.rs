@@ -0,0 +1,205 @@
+
+use nalgebra_glm::Vec3;
+use crate::exr::{Channel, Precision};
+
+// Pases que se pueden ver en la ventana y guardar junto a la imagen final
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Pass {
+    Beauty,  // La imagen final
+    Depth,
+    Normal,
+    Albedo,
+    MaterialId,
+    ObjectId,
+    Direct,
+    Indirect,
+    Occlusion,
+}
+
+impl Pass {
+    pub const ALL: [Pass; 9] = [
+        Pass::Beauty,
+        Pass::Depth,
+        Pass::Normal,
+        Pass::Albedo,
+        Pass::MaterialId,
+        Pass::ObjectId,
+        Pass::Direct,
+        Pass::Indirect,
+        Pass::Occlusion,
+    ];
+
+    // Siguiente pase en el ciclo de la tecla que los recorre
+    pub fn next(self) -> Pass {
+        let index = Pass::ALL.iter().position(|&pass| pass == self).unwrap_or(0);
+        Pass::ALL[(index + 1) % Pass::ALL.len()]
+    }
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Pass::Beauty => "beauty",
+            Pass::Depth => "depth",
+            Pass::Normal => "normal",
+            Pass::Albedo => "albedo",
+            Pass::MaterialId => "material_id",
+            Pass::ObjectId => "object_id",
+            Pass::Direct => "direct",
+            Pass::Indirect => "indirect",
+            Pass::Occlusion => "occlusion",
+        }
+    }
+}
+
+// Datos auxiliares del rayo primario de un píxel. Los colores están en 0..1 y la
+// iluminación indirecta es lo que no viene directamente de las luces ni de la emisión.
+#[derive(Debug, Clone, Copy)]
+pub struct AovSample {
+    pub depth: f32,  // Distancia a la intersección; infinita si el rayo no toca nada
+    pub normal: Vec3,
+    pub albedo: Vec3,
+    pub material_id: u32,
+    pub object_id: u32,  // Índice del objeto en la escena más uno; 0 es el cielo
+    pub direct: Vec3,
+    pub indirect: Vec3,
+    pub occlusion: f32,  // Oclusión ambiental: 1 sin tapar, 0 tapado del todo
+}
+
+impl Default for AovSample {
+    fn default() -> Self {
+        AovSample {
+            depth: f32::INFINITY,
+            normal: Vec3::zeros(),
+            albedo: Vec3::zeros(),
+            material_id: 0,
+            object_id: 0,
+            direct: Vec3::zeros(),
+            indirect: Vec3::zeros(),
+            occlusion: 1.0,
+        }
+    }
+}
+
+impl AovSample {
+    // Promedio de las muestras de un píxel (lente, obturador), para que los pases se desenfoquen
+    // igual que la imagen. Los identificadores son los de la primera muestra que toca algo.
+    pub fn average(samples: &[AovSample]) -> AovSample {
+        let Some(first) = samples.iter().find(|s| s.object_id != 0).or(samples.first()) else {
+            return AovSample::default();
+        };
+        let count = samples.len() as f32;
+        let hits: Vec<f32> = samples.iter().map(|s| s.depth).filter(|d| d.is_finite()).collect();
+        AovSample {
+            depth: if hits.is_empty() { f32::INFINITY } else { hits.iter().sum::<f32>() / hits.len() as f32 },
+            normal: samples.iter().map(|s| s.normal).sum::<Vec3>().try_normalize(1e-6).unwrap_or_else(Vec3::zeros),
+            albedo: samples.iter().map(|s| s.albedo).sum::<Vec3>() / count,
+            material_id: first.material_id,
+            object_id: first.object_id,
+            direct: samples.iter().map(|s| s.direct).sum::<Vec3>() / count,
+            indirect: samples.iter().map(|s| s.indirect).sum::<Vec3>() / count,
+            occlusion: samples.iter().map(|s| s.occlusion).sum::<f32>() / count,
+        }
+    }
+}
+
+pub struct AovBuffers {
+    pub width: usize,
+    pub height: usize,
+    pub pixels: Vec<AovSample>,
+    // El pase de oclusión cuesta un hemisferio de rayos por píxel: sin oclusión ambiental en
+    // la escena solo se calcula si se pide, no cuando los pases son solo para el filtro de ruido
+    pub occlusion: bool,
+}
+
+impl AovBuffers {
+    pub fn new(width: usize, height: usize) -> Self {
+        AovBuffers { width, height, pixels: vec![AovSample::default(); width * height], occlusion: false }
+    }
+
+    pub fn set(&mut self, x: usize, y: usize, sample: AovSample) {
+        if x < self.width && y < self.height {
+            self.pixels[y * self.width + x] = sample;
+        }
+    }
+
+    // Colores `0xRRGGBB` para mostrar o guardar el pase; `Beauty` no está aquí, es el framebuffer
+    pub fn visualize(&self, pass: Pass) -> Vec<u32> {
+        // La profundidad se normaliza a la mayor distancia finita: cerca claro, lejos oscuro
+        let max_depth = self.pixels.iter().map(|p| p.depth).filter(|d| d.is_finite()).fold(1e-3, f32::max);
+        self.pixels
+            .iter()
+            .map(|pixel| match pass {
+                Pass::Beauty => 0,
+                Pass::Depth if pixel.depth.is_finite() => {
+                    let value = 1.0 - pixel.depth / max_depth;
+                    to_hex(&Vec3::new(value, value, value))
+                }
+                Pass::Depth => 0,
+                Pass::Normal if pixel.object_id == 0 => 0,
+                Pass::Normal => to_hex(&(pixel.normal * 0.5).add_scalar(0.5)),
+                Pass::Albedo => to_hex(&pixel.albedo),
+                Pass::MaterialId => id_color(pixel.material_id),
+                Pass::ObjectId => id_color(pixel.object_id),
+                Pass::Direct => to_hex(&pixel.direct),
+                Pass::Indirect => to_hex(&pixel.indirect),
+                Pass::Occlusion => to_hex(&Vec3::new(pixel.occlusion, pixel.occlusion, pixel.occlusion)),
+            })
+            .collect()
+    }
+
+    // Guarda cada pase como `<prefijo>_<pase>.png`
+    pub fn save(&self, prefix: &str) -> image::ImageResult<()> {
+        for pass in Pass::ALL.into_iter().skip(1) {
+            let mut image = image::RgbImage::new(self.width as u32, self.height as u32);
+            for (i, pixel) in self.visualize(pass).into_iter().enumerate() {
+                let rgb = [(pixel >> 16) as u8, (pixel >> 8) as u8, pixel as u8];
+                image.put_pixel((i % self.width) as u32, (i / self.width) as u32, image::Rgb(rgb));
+            }
+            image.save(format!("{}_{}.png", prefix, pass.name()))?;
+        }
+        Ok(())
+    }
+
+    // Capas para OpenEXR; la profundidad va siempre en precisión completa
+    pub fn exr_channels(&self, precision: Precision) -> Vec<Channel> {
+        let mut channels = vec![
+            Channel::float("depth.Z", self.pixels.iter().map(|p| p.depth).collect(), Precision::Float),
+            Channel::float("occlusion.Y", self.pixels.iter().map(|p| p.occlusion).collect(), precision),
+            Channel::uint("materialId", self.pixels.iter().map(|p| p.material_id).collect()),
+            Channel::uint("objectId", self.pixels.iter().map(|p| p.object_id).collect()),
+        ];
+        for layer in [Pass::Normal, Pass::Albedo, Pass::Direct, Pass::Indirect] {
+            let names = if layer == Pass::Normal { ["X", "Y", "Z"] } else { ["R", "G", "B"] };
+            for (axis, name) in names.iter().enumerate() {
+                let values = self
+                    .pixels
+                    .iter()
+                    .map(|p| match layer {
+                        Pass::Normal => p.normal[axis],
+                        Pass::Albedo => p.albedo[axis],
+                        Pass::Direct => p.direct[axis],
+                        _ => p.indirect[axis],
+                    })
+                    .collect();
+                channels.push(Channel::float(&format!("{}.{}", layer.name(), name), values, precision));
+            }
+        }
+        channels
+    }
+}
+
+fn to_hex(color: &Vec3) -> u32 {
+    let [r, g, b] = [color.x, color.y, color.z].map(|c| (c.clamp(0.0, 1.0) * 255.0) as u32);
+    (r << 16) | (g << 8) | b
+}
+
+// Color estable y bien distinto para cada identificador; el 0 es negro
+fn id_color(id: u32) -> u32 {
+    if id == 0 {
+        return 0;
+    }
+    let mut hash = id.wrapping_mul(0x9E37_79B9);
+    hash ^= hash >> 15;
+    hash = hash.wrapping_mul(0x85EB_CA6B);
+    hash ^= hash >> 13;
+    (hash & 0xFFFFFF) | 0x404040
+}
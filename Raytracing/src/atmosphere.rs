@@ -0,0 +1,134 @@
+
+use nalgebra_glm::Vec3;
+use std::f32::consts::PI;
+use crate::color::Color;
+use crate::light::Light;
+
+// Niebla global que se mezcla con el color según el espesor óptico del rayo
+#[derive(Debug, Clone, Copy)]
+pub enum Fog {
+    // Densidad constante en todo el espacio
+    Exponential { color: Color, density: f32 },
+    // Densidad que decae exponencialmente con la altura a partir de `base_height`
+    Height { color: Color, density: f32, falloff: f32, base_height: f32 },
+}
+
+impl Fog {
+    // Espesor óptico del tramo de `distance` unidades desde `origin` en la dirección (unitaria) `direction`
+    pub fn optical_depth(&self, origin: &Vec3, direction: &Vec3, distance: f32) -> f32 {
+        match *self {
+            Fog::Exponential { density, .. } => {
+                if density <= 0.0 { 0.0 } else { density * distance }
+            }
+            Fog::Height { density, falloff, base_height, .. } => {
+                if density <= 0.0 {
+                    return 0.0;
+                }
+                // Integral analítica de density * exp(-falloff * (y - base_height)) a lo largo del rayo
+                let start = density * (-falloff * (origin.y - base_height)).exp();
+                let k = falloff * direction.y;
+                if k.abs() < 1e-4 {
+                    start * distance
+                } else {
+                    start * (1.0 - (-k * distance).exp()) / k
+                }
+            }
+        }
+    }
+
+    pub fn color(&self) -> Color {
+        match *self {
+            Fog::Exponential { color, .. } | Fog::Height { color, .. } => color,
+        }
+    }
+
+    pub fn set_color(&mut self, new_color: Color) {
+        match self {
+            Fog::Exponential { color, .. } | Fog::Height { color, .. } => *color = new_color,
+        }
+    }
+
+    // Radiancia vista a través de la niebla; `distance` puede ser infinita (rayos sin intersección)
+    pub fn apply(&self, radiance: Vec3, origin: &Vec3, direction: &Vec3, distance: f32) -> Vec3 {
+        let transmittance = (-self.optical_depth(origin, direction, distance)).exp();
+        radiance * transmittance + self.color().to_linear() * (1.0 - transmittance)
+    }
+}
+
+// Medio homogéneo dentro de una caja con dispersión simple: la luz de cada fuente que llega
+// a un punto del medio se desvía hacia la cámara, así se ven los haces entre las sombras
+#[derive(Debug, Clone, Copy)]
+pub struct ScatteringVolume {
+    pub min: Vec3,
+    pub max: Vec3,
+    pub scattering: f32,  // Coeficiente de dispersión por unidad de distancia
+    pub absorption: f32,  // Coeficiente de absorción por unidad de distancia
+    pub anisotropy: f32,  // Parámetro g de Henyey-Greenstein: > 0 dispersa hacia delante
+    pub steps: u32,       // Muestras de la marcha por rayo
+}
+
+impl ScatteringVolume {
+    pub fn new(min: Vec3, max: Vec3, scattering: f32, absorption: f32, anisotropy: f32) -> Self {
+        ScatteringVolume { min, max, scattering, absorption, anisotropy, steps: 32 }
+    }
+
+    fn extinction(&self) -> f32 {
+        self.scattering + self.absorption
+    }
+
+    // Tramo [entrada, salida] del rayo dentro de la caja, recortado a distancias positivas
+    fn clip(&self, origin: &Vec3, direction: &Vec3) -> Option<(f32, f32)> {
+        let inv_dir = Vec3::new(1.0, 1.0, 1.0).component_div(direction);
+        let tmin = (self.min - origin).component_mul(&inv_dir);
+        let tmax = (self.max - origin).component_mul(&inv_dir);
+        let t_near = tmin.x.min(tmax.x).max(tmin.y.min(tmax.y)).max(tmin.z.min(tmax.z)).max(0.0);
+        let t_far = tmin.x.max(tmax.x).min(tmin.y.max(tmax.y)).min(tmin.z.max(tmax.z));
+        if t_near < t_far { Some((t_near, t_far)) } else { None }
+    }
+
+    fn phase(&self, cos_theta: f32) -> f32 {
+        let g = self.anisotropy;
+        let denom = 1.0 + g * g - 2.0 * g * cos_theta;
+        (1.0 - g * g) / (4.0 * PI * denom * denom.sqrt())
+    }
+
+    // Marcha por el tramo del rayo dentro del medio hasta `distance`. Devuelve la transmitancia
+    // del tramo y la luz dispersada hacia la cámara; `visibility(punto, luz)` es la sombra
+    // de los objetos de la escena, y `jitter` (0..1) desplaza las muestras para evitar bandas.
+    pub fn march(&self, origin: &Vec3, direction: &Vec3, distance: f32, lights: &[Light], jitter: f32, visibility: impl Fn(&Vec3, &Light) -> f32) -> (f32, Vec3) {
+        let Some((t_enter, t_exit)) = self.clip(origin, direction) else {
+            return (1.0, Vec3::zeros());
+        };
+        let t_exit = t_exit.min(distance);
+        if t_exit <= t_enter {
+            return (1.0, Vec3::zeros());
+        }
+
+        let sigma_t = self.extinction();
+        let step = (t_exit - t_enter) / self.steps as f32;
+        let mut scattered = Vec3::zeros();
+        for i in 0..self.steps {
+            let t = t_enter + (i as f32 + jitter) * step;
+            let point = origin + direction * t;
+            let view_transmittance = (-sigma_t * (t - t_enter)).exp();
+
+            for light in lights.iter().filter(|light| light.intensity > 0.0) {
+                let to_light = light.position - point;
+                let light_dir = to_light.normalize();
+                let inside = self.clip(&point, &light_dir).map_or(0.0, |(_, exit)| exit.min(to_light.magnitude()));
+                let light_transmittance = (-sigma_t * inside).exp();
+                let incoming = light.color.to_linear() * light.intensity * light_transmittance * visibility(&point, light);
+                scattered += incoming * (self.scattering * self.phase(light_dir.dot(direction)) * view_transmittance * step);
+            }
+        }
+
+        ((-sigma_t * (t_exit - t_enter)).exp(), scattered)
+    }
+}
+
+// Todo lo que afecta a los rayos de cámara entre el ojo y la superficie
+#[derive(Debug, Clone, Default)]
+pub struct Atmosphere {
+    pub fog: Option<Fog>,
+    pub volume: Option<ScatteringVolume>,
+}
@@ -0,0 +1,229 @@
+
+use std::rc::Rc;
+use nalgebra_glm::Vec3;
+use crate::color::Color;
+use crate::cube::{Cube, CubeFace};
+use crate::material::Material;
+use crate::ray_intersect::{first_opaque, Intersect, Ray, RayIntersect, Span};
+use crate::texture::{self, Texture};
+
+pub type BlockId = u16;
+pub type MaterialId = u16;
+pub type TileId = u16;
+
+// Una sola imagen con todas las texturas de bloques en casillas del mismo tamaño
+#[derive(Debug, Clone)]
+pub struct TextureAtlas {
+    pub texture: Texture,
+    pub tile_size: u32,
+    columns: u32,
+    rows: u32,
+    tiles: u32,
+}
+
+impl TextureAtlas {
+    pub fn new(tile_size: u32, columns: u32, rows: u32) -> Self {
+        let (width, height) = (tile_size * columns, tile_size * rows);
+        TextureAtlas {
+            texture: Texture::without_mips(vec![0; (width * height * 4) as usize], width, height),
+            tile_size,
+            columns,
+            rows,
+            tiles: 0,
+        }
+    }
+
+    // Agrega una casilla cuyos píxeles calcula `pixel(x, y)` (0..tile_size) en RGBA
+    pub fn add_fn(&mut self, pixel: impl Fn(u32, u32) -> [u8; 4]) -> TileId {
+        assert!(self.tiles < self.columns * self.rows, "El atlas de texturas está lleno");
+        let tile = self.tiles;
+        let (origin_x, origin_y) = ((tile % self.columns) * self.tile_size, (tile / self.columns) * self.tile_size);
+        for y in 0..self.tile_size {
+            for x in 0..self.tile_size {
+                let idx = (((origin_y + y) * self.texture.width + origin_x + x) * 4) as usize;
+                self.texture.data[idx..idx + 4].copy_from_slice(&pixel(x, y));
+            }
+        }
+        self.tiles += 1;
+        tile as TileId
+    }
+
+    // Copia una textura reescalándola al tamaño de la casilla: cada texel promedia el bloque
+    // de la fuente que cubre, así que al reducir no se pierde detalle por aliasing
+    pub fn add_texture(&mut self, texture: &Texture) -> TileId {
+        let size = self.tile_size;
+        self.add_fn(|x, y| box_texel(texture, x, y, size))
+    }
+
+    // Genera los mipmaps una sola vez, después de agregar todas las casillas
+    pub fn finish(&mut self) {
+        self.texture.build_mipmaps();
+    }
+
+    pub fn add_color(&mut self, color: Color) -> TileId {
+        let rgb = color.to_hex();
+        self.add_fn(|_, _| [(rgb >> 16) as u8, (rgb >> 8) as u8, rgb as u8, 255])
+    }
+
+    pub fn sample_alpha(&self, tile: TileId, u: f32, v: f32) -> f32 {
+        let (x, y) = self.texel_coords(tile, u, v);
+        self.texture.get_alpha(x, y)
+    }
+
+    // Coordenadas normalizadas en el atlas del centro del texel (u, v) de la casilla
+    fn texel_coords(&self, tile: TileId, u: f32, v: f32) -> (f32, f32) {
+        let tile = tile as u32;
+        let size = self.tile_size as f32;
+        let x = (u.rem_euclid(1.0) * size).min(size - 1.0).floor() + ((tile % self.columns) * self.tile_size) as f32;
+        let y = (v.rem_euclid(1.0) * size).min(size - 1.0).floor() + ((tile / self.columns) * self.tile_size) as f32;
+        ((x + 0.5) / self.texture.width as f32, (y + 0.5) / self.texture.height as f32)
+    }
+
+    // Color de la casilla `tile` en las coordenadas UV con el filtro y la repetición del atlas.
+    // El nivel de detalle se limita para que los mipmaps no mezclen casillas vecinas.
+    pub fn sample(&self, tile: TileId, u: f32, v: f32, lod: f32) -> Color {
+        let tile = tile as u32;
+        let rect = ((tile % self.columns) * self.tile_size, (tile / self.columns) * self.tile_size, self.tile_size, self.tile_size);
+        let max_lod = (self.tile_size as f32).log2();
+        let [r, g, b, _] = self.texture.sample_region(rect, u, v, lod.min(max_lod));
+        Color::new(r as u8, g as u8, b as u8)
+    }
+}
+
+// Promedio de los texels de `texture` que caen en el texel (x, y) de una casilla de `size`
+// texels. Si la fuente es más chica que la casilla el bloque es un solo texel.
+fn box_texel(texture: &Texture, x: u32, y: u32, size: u32) -> [u8; 4] {
+    let span = |i: u32, extent: u32| {
+        let start = i * extent / size;
+        (start, ((i + 1) * extent / size).max(start + 1))
+    };
+    let ((x0, x1), (y0, y1)) = (span(x, texture.width), span(y, texture.height));
+    let mut sum = [0u32; 4];
+    for sy in y0..y1 {
+        for sx in x0..x1 {
+            let idx = ((sy * texture.width + sx) * 4) as usize;
+            for (total, &value) in sum.iter_mut().zip(&texture.data[idx..idx + 4]) {
+                *total += value as u32;
+            }
+        }
+    }
+    let count = (x1 - x0) * (y1 - y0);
+    sum.map(|c| (c / count) as u8)
+}
+
+// Un tipo de bloque: la casilla del atlas de cada cara y el material compartido
+#[derive(Debug, Clone)]
+pub struct BlockType {
+    pub name: String,
+    pub faces: [TileId; 6],  // En el orden de `CubeFace`
+    pub material: MaterialId,
+}
+
+#[derive(Debug, Clone)]
+pub struct BlockRegistry {
+    pub atlas: TextureAtlas,
+    materials: Vec<Material>,
+    blocks: Vec<BlockType>,
+}
+
+impl BlockRegistry {
+    pub fn new(atlas: TextureAtlas) -> Self {
+        BlockRegistry { atlas, materials: Vec::new(), blocks: Vec::new() }
+    }
+
+    pub fn add_material(&mut self, material: Material) -> MaterialId {
+        self.materials.push(material);
+        (self.materials.len() - 1) as MaterialId
+    }
+
+    // Registra un bloque con texturas distintas arriba, a los lados y abajo
+    pub fn register(&mut self, name: &str, material: MaterialId, top: TileId, side: TileId, bottom: TileId) -> BlockId {
+        self.blocks.push(BlockType {
+            name: name.to_string(),
+            faces: [side, side, bottom, top, side, side],
+            material,
+        });
+        (self.blocks.len() - 1) as BlockId
+    }
+
+    pub fn get(&self, id: BlockId) -> &BlockType {
+        &self.blocks[id as usize]
+    }
+
+    // Indica si el texel de la cara queda recortado por el alfa del atlas
+    pub fn is_cut(&self, id: BlockId, face: CubeFace, (u, v): (f32, f32)) -> bool {
+        let block = self.get(id);
+        match self.materials[block.material as usize].alpha_cutoff {
+            Some(cutoff) => self.atlas.sample_alpha(block.faces[face as usize], u, v) < cutoff,
+            None => false,
+        }
+    }
+
+    pub fn has_cutout(&self, id: BlockId) -> bool {
+        self.materials[self.get(id).material as usize].alpha_cutoff.is_some()
+    }
+
+    // Bloques de un medio transparente (agua): los bloques contiguos forman un solo volumen
+    pub fn is_dielectric(&self, id: BlockId) -> bool {
+        self.materials[self.get(id).material as usize].dielectric.is_some()
+    }
+
+    // Material de una cara del bloque con el color difuso tomado del atlas
+    pub fn surface_material(&self, id: BlockId, face: CubeFace, (u, v): (f32, f32), lod: f32) -> Material {
+        let block = self.get(id);
+        let mut material = self.materials[block.material as usize].clone();
+        material.diffuse = self.atlas.sample(block.faces[face as usize], u, v, lod);
+        material
+    }
+}
+
+// Cubo que solo guarda el identificador de su bloque; el material sale del registro compartido
+#[derive(Debug, Clone)]
+pub struct BlockCube {
+    pub bounds: Cube,
+    pub block: BlockId,
+    pub registry: Rc<BlockRegistry>,
+}
+
+impl BlockCube {
+    pub fn new(min: Vec3, max: Vec3, block: BlockId, registry: &Rc<BlockRegistry>) -> Self {
+        BlockCube {
+            bounds: Cube::new(min, max, Material::black(), false),
+            block,
+            registry: Rc::clone(registry),
+        }
+    }
+
+    fn shade(&self, mut hit: Intersect, ray: &Ray) -> Intersect {
+        let face = self.bounds.get_face(&hit.point);
+        let extent = self.bounds.max - self.bounds.min;
+        let texels_per_unit = self.registry.atlas.tile_size as f32 / extent.x.max(extent.y).max(extent.z);
+        let cos_theta = hit.normal.dot(&ray.direction.normalize());
+        let lod = texture::lod(ray.footprint(hit.distance * ray.direction.magnitude()), cos_theta, texels_per_unit);
+        hit.material = self.registry.surface_material(self.block, face, self.bounds.get_uv(&hit.point), lod);
+        hit
+    }
+}
+
+impl RayIntersect for BlockCube {
+    fn ray_intersect(&self, ray: &Ray) -> Intersect {
+        if self.registry.has_cutout(self.block) {
+            let hit = first_opaque(self.bounds.ray_spans(ray), |hit| {
+                let face = self.bounds.get_face(&hit.point);
+                self.registry.is_cut(self.block, face, self.bounds.get_uv(&hit.point))
+            });
+            return if hit.is_intersecting { self.shade(hit, ray) } else { hit };
+        }
+
+        let hit = self.bounds.ray_intersect(ray);
+        if hit.is_intersecting { self.shade(hit, ray) } else { hit }
+    }
+
+    fn ray_spans(&self, ray: &Ray) -> Vec<Span> {
+        self.bounds
+            .ray_spans(ray)
+            .into_iter()
+            .map(|span| Span { enter: self.shade(span.enter, ray), exit: self.shade(span.exit, ray) })
+            .collect()
+    }
+}
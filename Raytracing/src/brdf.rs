@@ -0,0 +1,85 @@
+
+// BRDF de Cook-Torrance con distribución GGX (flujo metallic-roughness).
+// Todas las rutas de render evalúan la reflexión con estas funciones para que coincidan.
+
+use nalgebra_glm::Vec3;
+use std::f32::consts::PI;
+
+// Rugosidad mínima: evita el pico infinito de GGX en superficies perfectamente lisas
+const MIN_ROUGHNESS: f32 = 0.04;
+
+// Reflectancia a incidencia normal de los dieléctricos (≈ 4%)
+const DIELECTRIC_F0: f32 = 0.04;
+
+// Distribución de microfacetas GGX / Trowbridge-Reitz
+pub fn ggx_distribution(n_dot_h: f32, roughness: f32) -> f32 {
+    let alpha = roughness.max(MIN_ROUGHNESS).powi(2);
+    let alpha2 = alpha * alpha;
+    let d = n_dot_h * n_dot_h * (alpha2 - 1.0) + 1.0;
+    alpha2 / (PI * d * d)
+}
+
+// Término de sombreado-enmascaramiento de Smith con la aproximación de Schlick-GGX
+pub fn smith_geometry(n_dot_v: f32, n_dot_l: f32, roughness: f32) -> f32 {
+    let k = (roughness.max(MIN_ROUGHNESS) + 1.0).powi(2) / 8.0;
+    let g1 = |n_dot_x: f32| n_dot_x / (n_dot_x * (1.0 - k) + k);
+    g1(n_dot_v) * g1(n_dot_l)
+}
+
+pub fn fresnel_schlick(cos_theta: f32, f0: &Vec3) -> Vec3 {
+    let factor = (1.0 - cos_theta).clamp(0.0, 1.0).powi(5);
+    f0 + (Vec3::new(1.0, 1.0, 1.0) - f0) * factor
+}
+
+// Fresnel promediado sobre el lóbulo: las superficies rugosas reflejan menos en los bordes
+pub fn fresnel_schlick_roughness(cos_theta: f32, f0: &Vec3, roughness: f32) -> Vec3 {
+    let factor = (1.0 - cos_theta).clamp(0.0, 1.0).powi(5);
+    let max = Vec3::new(1.0 - roughness, 1.0 - roughness, 1.0 - roughness).sup(f0);
+    f0 + (max - f0) * factor
+}
+
+// Reflectancia especular a incidencia normal: 4% en dieléctricos, el color base en metales
+pub fn base_reflectance(base_color: &Vec3, metallic: f32) -> Vec3 {
+    let dielectric = Vec3::new(DIELECTRIC_F0, DIELECTRIC_F0, DIELECTRIC_F0);
+    dielectric.lerp(base_color, metallic)
+}
+
+// Radiancia reflejada hacia `view_dir` por unidad de irradiancia que llega desde `light_dir`
+// (BRDF por el coseno n·l). La parte difusa se reduce con lo que ya refleja la especular,
+// así la energía total nunca supera la incidente.
+pub fn cook_torrance(normal: &Vec3, view_dir: &Vec3, light_dir: &Vec3, base_color: &Vec3, metallic: f32, roughness: f32) -> Vec3 {
+    let n_dot_l = normal.dot(light_dir);
+    let n_dot_v = normal.dot(view_dir);
+    if n_dot_l <= 0.0 || n_dot_v <= 0.0 {
+        return Vec3::zeros();
+    }
+
+    let half = (view_dir + light_dir).normalize();
+    let n_dot_h = normal.dot(&half).max(0.0);
+    let v_dot_h = view_dir.dot(&half).max(0.0);
+
+    let f0 = base_reflectance(base_color, metallic);
+    let fresnel = fresnel_schlick(v_dot_h, &f0);
+    let d = ggx_distribution(n_dot_h, roughness);
+    let g = smith_geometry(n_dot_v, n_dot_l, roughness);
+    let specular = fresnel * (d * g / (4.0 * n_dot_v * n_dot_l));
+
+    let kd = (Vec3::new(1.0, 1.0, 1.0) - fresnel) * (1.0 - metallic);
+    let diffuse = kd.component_mul(base_color) / PI;
+
+    (diffuse + specular) * n_dot_l
+}
+
+// Reflectancia de Fresnel exacta (luz no polarizada) en la frontera entre dos dieléctricos.
+// `eta` es el cociente de índices n1 / n2; devuelve 1.0 con reflexión interna total.
+pub fn fresnel_dielectric(cos_i: f32, eta: f32) -> f32 {
+    let cos_i = cos_i.clamp(0.0, 1.0);
+    let sin_t2 = eta * eta * (1.0 - cos_i * cos_i);
+    if sin_t2 >= 1.0 {
+        return 1.0;
+    }
+    let cos_t = (1.0 - sin_t2).sqrt();
+    let r_s = (eta * cos_i - cos_t) / (eta * cos_i + cos_t);
+    let r_p = (cos_i - eta * cos_t) / (cos_i + eta * cos_t);
+    0.5 * (r_s * r_s + r_p * r_p)
+}
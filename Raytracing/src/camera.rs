@@ -2,10 +2,77 @@
 use nalgebra_glm::Vec3;
 use std::f32::consts::PI;
 
+// Forma de la apertura del diafragma, que es la forma que toman los desenfoques (bokeh)
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Bokeh {
+    Circle,
+    Polygon { blades: u32, rotation: f32 },  // Diafragma de `blades` láminas rectas
+}
+
+// Eje sobre el que se mide el campo de visión
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FovAxis {
+    Horizontal,
+    Vertical,
+}
+
+// Cómo se proyecta cada píxel en un rayo
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Projection {
+    Perspective,
+    // Rayos paralelos; `half_height` es media altura de la vista en unidades del mundo
+    Orthographic { half_height: f32 },
+    // Ojo de pez equidistante: el ángulo crece linealmente con la distancia al centro
+    FisheyeEquidistant,
+    // Ojo de pez equisólido: conserva las áreas, comprime menos el borde
+    FisheyeEquisolid,
+    // Panorámica de 360° x 180° (longitud en x, latitud en y) para visores de realidad virtual
+    Equirectangular,
+}
+
+// Cómo se reparten las vistas de los dos ojos en la imagen
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum StereoLayout {
+    SideBySide,  // Ojo izquierdo a la izquierda, derecho a la derecha
+    OverUnder,   // Ojo izquierdo arriba, derecho abajo (habitual en panorámicas estéreo)
+    Anaglyph,    // Las dos vistas superpuestas en rojo y cian
+}
+
+// Par de cámaras separadas por la distancia interpupilar, con el plano de pantalla (donde
+// la paralaje es nula) a la distancia de convergencia
+#[derive(Debug, Clone, Copy)]
+pub struct StereoRig {
+    pub ipd: f32,
+    pub convergence: f32,
+    pub layout: StereoLayout,
+}
+
+// Obturador abierto durante `duration` segundos que terminan en el instante del cuadro;
+// la cámara va de la pose de apertura a la suya propia mientras está abierto
+#[derive(Debug, Clone, Copy)]
+pub struct Shutter {
+    pub open_eye: Vec3,
+    pub open_center: Vec3,
+    pub duration: f32,
+}
+
+#[derive(Debug, Clone)]
 pub struct Camera {
     pub eye: Vec3,
     pub center: Vec3,
-    pub up: Vec3
+    pub up: Vec3,
+    pub aperture: f32,        // Radio de la lente; 0 es una cámara estenopeica sin desenfoque
+    pub focus_distance: f32,  // Distancia al plano enfocado, medida a lo largo de la dirección de vista
+    pub bokeh: Bokeh,
+    pub lens_samples: u32,    // Rayos por píxel repartidos sobre la lente
+    pub autofocus: bool,      // Si está, el foco se ajusta a lo que haya en el centro de la pantalla
+    pub fov: f32,             // Campo de visión en radianes (en ojo de pez, el ángulo del círculo completo)
+    pub fov_axis: FovAxis,
+    pub projection: Projection,
+    pub shutter: Option<Shutter>,  // Sin obturador no hay desenfoque de movimiento
+    pub motion_samples: u32,       // Instantes por píxel repartidos sobre el obturador
+    pub stereo: Option<StereoRig>,
+    eye_offset: f32,               // Desplazamiento del ojo hacia la derecha; 0 en la cámara central
 }
 
 impl Camera {
@@ -13,10 +80,176 @@ impl Camera {
         Camera {
             eye,
             center,
-            up
+            up,
+            aperture: 0.0,
+            focus_distance: (center - eye).magnitude(),
+            bokeh: Bokeh::Circle,
+            lens_samples: 16,
+            autofocus: false,
+            fov: PI / 2.0,
+            fov_axis: FovAxis::Vertical,
+            projection: Projection::Perspective,
+            shutter: None,
+            motion_samples: 8,
+            stereo: None,
+            eye_offset: 0.0,
         }
     }
 
+    pub fn with_projection(mut self, projection: Projection, fov: f32, fov_axis: FovAxis) -> Self {
+        self.projection = projection;
+        self.fov = fov;
+        self.fov_axis = fov_axis;
+        self
+    }
+
+    // Abre o cierra la vista: el ángulo en las proyecciones angulares, el tamaño en la ortográfica
+    pub fn adjust_fov(&mut self, factor: f32) {
+        match &mut self.projection {
+            Projection::Orthographic { half_height } => *half_height = (*half_height * factor).max(0.1),
+            Projection::Equirectangular => {}
+            _ => self.fov = (self.fov * factor).clamp(0.05, 2.0 * PI),
+        }
+    }
+
+    // Medio ancho y media alto de la vista según el eje del FOV; `tangent` es la mitad
+    // del tamaño sobre ese eje (la tangente en perspectiva, el ángulo en ojo de pez)
+    fn half_extents(&self, tangent: f32, aspect_ratio: f32) -> (f32, f32) {
+        match self.fov_axis {
+            FovAxis::Vertical => (tangent * aspect_ratio, tangent),
+            FovAxis::Horizontal => (tangent, tangent / aspect_ratio),
+        }
+    }
+
+    // Rayo primario (origen y dirección en el mundo) para el punto `(x, y)` de la pantalla en
+    // -1..1; `None` fuera de la imagen circular de los ojos de pez
+    pub fn primary_ray(&self, x: f32, y: f32, aspect_ratio: f32) -> Option<(Vec3, Vec3)> {
+        let local = match self.projection {
+            Projection::Perspective => {
+                let (half_width, half_height) = self.half_extents((self.fov * 0.5).tan(), aspect_ratio);
+                // Frustum asimétrico: los dos ojos comparten la ventana a la distancia de convergencia
+                let shift = self.stereo.map_or(0.0, |rig| self.eye_offset / rig.convergence.max(1e-3));
+                Vec3::new(x * half_width - shift, y * half_height, -1.0)
+            }
+            Projection::Orthographic { half_height } => {
+                let forward = self.forward();
+                let right = forward.cross(&self.up).normalize();
+                let up = right.cross(&forward).normalize();
+                let origin = self.eye + right * (x * half_height * aspect_ratio + self.eye_offset) + up * (y * half_height);
+                return Some((origin, forward));
+            }
+            Projection::FisheyeEquidistant | Projection::FisheyeEquisolid => {
+                // Coordenadas normalizadas al radio del círculo de la imagen sobre el eje del FOV
+                let (half_width, half_height) = self.half_extents(1.0, aspect_ratio);
+                let (px, py) = (x * half_width, y * half_height);
+                let r = (px * px + py * py).sqrt();
+                if r > 1.0 {
+                    return None;
+                }
+                let theta = if self.projection == Projection::FisheyeEquidistant {
+                    r * self.fov * 0.5
+                } else {
+                    2.0 * (r * (self.fov * 0.25).sin()).clamp(-1.0, 1.0).asin()
+                };
+                let phi = py.atan2(px);
+                Vec3::new(theta.sin() * phi.cos(), theta.sin() * phi.sin(), -theta.cos())
+            }
+            Projection::Equirectangular => {
+                let longitude = x * PI;
+                let latitude = y * PI * 0.5;
+                let direction = Vec3::new(latitude.cos() * longitude.sin(), latitude.sin(), -latitude.cos() * longitude.cos());
+                // Estéreo omnidireccional: cada rayo sale de un círculo de diámetro `ipd`, desplazado
+                // a la derecha de su propia dirección horizontal, así hay relieve en toda la vuelta
+                let side = self.base_change(&Vec3::new(longitude.cos(), 0.0, longitude.sin()));
+                return Some((self.eye + side * self.eye_offset, self.base_change(&direction)));
+            }
+        };
+        // En los ojos de pez los dos ojos miran en paralelo
+        let right = self.base_change(&Vec3::new(1.0, 0.0, 0.0));
+        Some((self.eye + right * self.eye_offset, self.base_change(&local.normalize())))
+    }
+
+    // Ángulo aproximado que cubre un píxel, para elegir el nivel de mipmap; 0 en la
+    // ortográfica, donde la huella del píxel no crece con la distancia
+    pub fn pixel_spread(&self, height: f32, aspect_ratio: f32) -> f32 {
+        match self.projection {
+            Projection::Perspective => 2.0 * self.half_extents((self.fov * 0.5).tan(), aspect_ratio).1 / height,
+            Projection::Orthographic { .. } => 0.0,
+            Projection::FisheyeEquidistant | Projection::FisheyeEquisolid => self.fov * self.half_extents(1.0, aspect_ratio).1 / height,
+            Projection::Equirectangular => PI / height,
+        }
+    }
+
+    pub fn with_lens(mut self, aperture: f32, focus_distance: f32, bokeh: Bokeh) -> Self {
+        self.aperture = aperture;
+        self.focus_distance = focus_distance;
+        self.bokeh = bokeh;
+        self
+    }
+
+    // Cámara de un ojo del par estéreo: `side` es -1 para el izquierdo y 1 para el derecho
+    pub fn for_eye(&self, side: f32) -> Camera {
+        let mut camera = self.clone();
+        camera.eye_offset = self.stereo.map_or(0.0, |rig| side * rig.ipd * 0.5);
+        camera
+    }
+
+    // Copia de la cámara en el punto `s` del obturador (0 al abrirse, 1 al cerrarse)
+    pub fn at_shutter(&self, s: f32) -> Camera {
+        let mut camera = self.clone();
+        if let Some(shutter) = self.shutter {
+            camera.eye = shutter.open_eye.lerp(&self.eye, s);
+            camera.center = shutter.open_center.lerp(&self.center, s);
+        }
+        camera
+    }
+
+    pub fn forward(&self) -> Vec3 {
+        (self.center - self.eye).normalize()
+    }
+
+    // Punto de la lente (en unidades del radio) para la muestra `(u, v)` en 0..1
+    fn lens_point(&self, u: f32, v: f32) -> (f32, f32) {
+        match self.bokeh {
+            Bokeh::Circle => {
+                let r = u.sqrt();
+                let theta = 2.0 * PI * v;
+                (r * theta.cos(), r * theta.sin())
+            }
+            Bokeh::Polygon { blades, rotation } => {
+                // Se elige una lámina y se muestrea uniformemente el triángulo que forma con el centro
+                let blades = blades.max(3);
+                let scaled = u * blades as f32;
+                let blade = (scaled.floor() as u32).min(blades - 1);
+                let a = (scaled - blade as f32).sqrt();
+                let corner = |i: u32| {
+                    let angle = rotation + 2.0 * PI * i as f32 / blades as f32;
+                    (angle.cos(), angle.sin())
+                };
+                let (p0, p1) = (corner(blade), corner(blade + 1));
+                (a * ((1.0 - v) * p0.0 + v * p1.0), a * ((1.0 - v) * p0.1 + v * p1.1))
+            }
+        }
+    }
+
+    // Rayo de lente delgada: sale de un punto de la lente y pasa por el punto enfocado del
+    // rayo primario (`origin`, `direction`). Devuelve origen y dirección.
+    pub fn lens_ray(&self, origin: &Vec3, direction: &Vec3, u: f32, v: f32) -> (Vec3, Vec3) {
+        if self.aperture <= 0.0 {
+            return (*origin, *direction);
+        }
+        let forward = self.forward();
+        let right = forward.cross(&self.up).normalize();
+        let up = right.cross(&forward).normalize();
+
+        // Con proyecciones de gran angular el rayo puede ir de lado o hacia atrás: se enfoca a lo largo del propio rayo
+        let along = direction.dot(&forward);
+        let focus_point = origin + direction * (self.focus_distance / if along > 0.1 { along } else { 1.0 });
+        let (lx, ly) = self.lens_point(u, v);
+        let origin = origin + (right * lx + up * ly) * self.aperture;
+        (origin, (focus_point - origin).normalize())
+    }
+
     // Función para acercar la cámara
     pub fn zoom_in(&mut self, amount: f32) {
         let direction = (self.center - self.eye).normalize();
@@ -37,7 +270,29 @@ impl Camera {
 
         let rotated = vector.x * right + vector.y * up - vector.z * forward;
 
-        return rotated.normalize();
+        rotated.normalize()
+    }
+
+    // Desplaza ojo y objetivo juntos, en primera persona: `forward` y `right` sobre el plano
+    // horizontal (como al caminar), `up` en vertical
+    pub fn fly(&mut self, forward: f32, right: f32, up: f32) {
+        let view = self.forward();
+        let flat_forward = Vec3::new(view.x, 0.0, view.z).try_normalize(1e-6).unwrap_or(Vec3::new(0.0, 0.0, -1.0));
+        let flat_right = flat_forward.cross(&Vec3::new(0.0, 1.0, 0.0));
+        let offset = flat_forward * forward + flat_right * right + Vec3::new(0.0, up, 0.0);
+        self.eye += offset;
+        self.center += offset;
+    }
+
+    // Gira la mirada alrededor del ojo (al revés que `orbit`, que gira el ojo alrededor del objetivo).
+    // Se conserva la distancia al objetivo para poder volver a orbitar alrededor de él.
+    pub fn look(&mut self, delta_yaw: f32, delta_pitch: f32) {
+        let view = self.center - self.eye;
+        let distance = view.magnitude();
+        let yaw = view.x.atan2(-view.z) + delta_yaw;
+        let pitch = ((view.y / distance).asin() + delta_pitch).clamp(-PI / 2.0 + 0.1, PI / 2.0 - 0.1);
+        let direction = Vec3::new(yaw.sin() * pitch.cos(), pitch.sin(), -yaw.cos() * pitch.cos());
+        self.center = self.eye + direction * distance;
     }
 
     pub fn orbit(&mut self, delta_yaw: f32, delta_pitch: f32) {
@@ -0,0 +1,30 @@
+
+// Reloj simulado del ciclo de día y noche, independiente de la velocidad de cuadros
+#[derive(Debug, Clone, Copy)]
+pub struct Clock {
+    pub hours: f32,  // Hora local, 0..24
+    pub speed: f32,  // Horas simuladas por segundo real
+    pub paused: bool,
+}
+
+impl Clock {
+    pub fn new(hours: f32, speed: f32) -> Self {
+        Clock { hours: hours.rem_euclid(24.0), speed, paused: false }
+    }
+
+    // Avanza el reloj `seconds` segundos reales multiplicados por `multiplier` (avance rápido)
+    pub fn advance(&mut self, seconds: f32, multiplier: f32) {
+        if !self.paused {
+            self.hours = (self.hours + self.speed * multiplier * seconds).rem_euclid(24.0);
+        }
+    }
+
+    pub fn toggle_pause(&mut self) {
+        self.paused = !self.paused;
+    }
+
+    // Duplica o divide la velocidad; negativo hace correr el tiempo hacia atrás
+    pub fn scale_speed(&mut self, factor: f32) {
+        self.speed *= factor;
+    }
+}
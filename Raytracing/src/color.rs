@@ -1,4 +1,5 @@
 use std::fmt;
+use nalgebra_glm::Vec3;
 
 #[derive(Debug, Clone, Copy)]
 pub struct Color {
@@ -9,7 +10,7 @@ pub struct Color {
 
 impl Color {
     // Constructor to initialize the color using r, g, b values
-    pub fn new(r: u8, g: u8, b: u8) -> Self {
+    pub const fn new(r: u8, g: u8, b: u8) -> Self {
         Color { r, g, b }
     }
 
@@ -21,12 +22,46 @@ impl Color {
         Color { r, g, b }
     }
 
+    // Color as linear floats in the 0.0 - 1.0 range per channel
+    pub fn to_vec3(self) -> Vec3 {
+        Vec3::new(self.r as f32, self.g as f32, self.b as f32) / 255.0
+    }
+
+    // Build a color from 0.0 - 1.0 floats, clamping out-of-range values
+    pub fn from_vec3(v: &Vec3) -> Self {
+        Color {
+            r: (v.x * 255.0).clamp(0.0, 255.0) as u8,
+            g: (v.y * 255.0).clamp(0.0, 255.0) as u8,
+            b: (v.z * 255.0).clamp(0.0, 255.0) as u8,
+        }
+    }
+
+    // Color tal como se ve en pantalla (sRGB) pasado a radiancia lineal, para los colores de los
+    // materiales, las texturas y las luces
+    pub fn to_linear(self) -> Vec3 {
+        self.to_vec3().map(srgb_decode)
+    }
+
+    // Color de pantalla (sRGB) a partir de radiancia lineal, recortando lo que pase de 1
+    pub fn from_linear(v: &Vec3) -> Self {
+        Color::from_vec3(&v.map(srgb_encode))
+    }
+
     // Function to return the color as a hex value
-    pub fn to_hex(&self) -> u32 {
+    pub fn to_hex(self) -> u32 {
         ((self.r as u32) << 16) | ((self.g as u32) << 8) | (self.b as u32)
     }
 }
 
+// Funciones de transferencia de sRGB entre valores lineales 0..1 y los que espera la pantalla
+pub fn srgb_encode(x: f32) -> f32 {
+    if x <= 0.003_130_8 { 12.92 * x } else { 1.055 * x.max(0.0).powf(1.0 / 2.4) - 0.055 }
+}
+
+pub fn srgb_decode(x: f32) -> f32 {
+    if x <= 0.040_45 { x / 12.92 } else { ((x + 0.055) / 1.055).powf(2.4) }
+}
+
 // Implement addition for Color
 use std::ops::Add;
 
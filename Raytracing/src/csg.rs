@@ -0,0 +1,170 @@
+
+use crate::ray_intersect::{Intersect, Ray, RayIntersect, Span};
+
+// Distancia mínima para aceptar una intersección (evita auto-intersecciones)
+const EPSILON: f32 = 1e-4;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum CsgOp {
+    Union,
+    Intersection,
+    Difference,
+}
+
+impl CsgOp {
+    // Decide si un punto está dentro del resultado según si está dentro de cada operando
+    fn contains(self, inside_left: bool, inside_right: bool) -> bool {
+        match self {
+            CsgOp::Union => inside_left || inside_right,
+            CsgOp::Intersection => inside_left && inside_right,
+            CsgOp::Difference => inside_left && !inside_right,
+        }
+    }
+}
+
+// Nodo de geometría constructiva: combina dos sólidos con una operación booleana
+pub struct Csg {
+    pub op: CsgOp,
+    pub left: Box<dyn RayIntersect>,
+    pub right: Box<dyn RayIntersect>,
+}
+
+impl Csg {
+    pub fn new(op: CsgOp, left: Box<dyn RayIntersect>, right: Box<dyn RayIntersect>) -> Self {
+        Csg { op, left, right }
+    }
+
+    pub fn union(left: impl RayIntersect + 'static, right: impl RayIntersect + 'static) -> Self {
+        Csg::new(CsgOp::Union, Box::new(left), Box::new(right))
+    }
+
+    pub fn intersection(left: impl RayIntersect + 'static, right: impl RayIntersect + 'static) -> Self {
+        Csg::new(CsgOp::Intersection, Box::new(left), Box::new(right))
+    }
+
+    // Resta `right` de `left` (útil para puertas, ventanas y cuencos)
+    pub fn difference(left: impl RayIntersect + 'static, right: impl RayIntersect + 'static) -> Self {
+        Csg::new(CsgOp::Difference, Box::new(left), Box::new(right))
+    }
+}
+
+// Un borde de tramo a lo largo del rayo, marcado con el operando del que viene
+struct Event {
+    hit: Intersect,
+    from_left: bool,
+    entering: bool,
+}
+
+fn combine(op: CsgOp, left: Vec<Span>, right: Vec<Span>) -> Vec<Span> {
+    let mut events = Vec::with_capacity(2 * (left.len() + right.len()));
+    for (spans, from_left) in [(left, true), (right, false)] {
+        for span in spans {
+            events.push(Event { hit: span.enter, from_left, entering: true });
+            events.push(Event { hit: span.exit, from_left, entering: false });
+        }
+    }
+    events.sort_by(|a, b| a.hit.distance.total_cmp(&b.hit.distance));
+
+    let mut spans = Vec::new();
+    let mut inside_left = false;
+    let mut inside_right = false;
+    let mut enter: Option<Intersect> = None;
+
+    for event in events {
+        let was_inside = op.contains(inside_left, inside_right);
+        if event.from_left {
+            inside_left = event.entering;
+        } else {
+            inside_right = event.entering;
+        }
+        let is_inside = op.contains(inside_left, inside_right);
+        if was_inside == is_inside {
+            continue;
+        }
+
+        let mut hit = event.hit;
+        // Al restar, la superficie del operando derecho queda invertida
+        if op == CsgOp::Difference && !event.from_left {
+            hit.normal = -hit.normal;
+        }
+
+        if is_inside {
+            enter = Some(hit);
+        } else if let Some(enter) = enter.take() {
+            spans.push(Span { enter, exit: hit });
+        }
+    }
+
+    spans
+}
+
+impl RayIntersect for Csg {
+    fn ray_intersect(&self, ray: &Ray) -> Intersect {
+        for span in self.ray_spans(ray) {
+            if span.enter.distance > EPSILON {
+                return span.enter;
+            }
+            if span.exit.distance > EPSILON {
+                return span.exit;  // El origen está dentro del sólido
+            }
+        }
+
+        Intersect::empty()
+    }
+
+    fn ray_spans(&self, ray: &Ray) -> Vec<Span> {
+        let left = self.left.ray_spans(ray);
+        let right = self.right.ray_spans(ray);
+        combine(self.op, left, right)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use nalgebra_glm::Vec3;
+    use crate::material::Material;
+    use crate::sphere::Sphere;
+
+    // Esferas de radio 1 centradas en el eje x; el rayo lo recorre desde x = -5, así que la
+    // esfera en x = 0 ocupa las distancias 4..6 y la de x = 1, 5..7
+    fn spheres(op: CsgOp, left_x: f32, right_x: f32) -> Csg {
+        let sphere = |x: f32| Sphere { center: Vec3::new(x, 0.0, 0.0), radius: 1.0, material: Material::black() };
+        Csg::new(op, Box::new(sphere(left_x)), Box::new(sphere(right_x)))
+    }
+
+    fn ray() -> Ray {
+        Ray::new(Vec3::new(-5.0, 0.0, 0.0), Vec3::new(1.0, 0.0, 0.0), 0.0)
+    }
+
+    // Distancias y componente x de la normal de la entrada y la salida de cada tramo
+    fn edges(spans: &[Span]) -> Vec<(f32, f32, f32, f32)> {
+        spans.iter().map(|span| (span.enter.distance, span.enter.normal.x, span.exit.distance, span.exit.normal.x)).collect()
+    }
+
+    #[test]
+    fn union_spans_both_spheres() {
+        assert_eq!(edges(&spheres(CsgOp::Union, 0.0, 1.0).ray_spans(&ray())), vec![(4.0, -1.0, 7.0, 1.0)]);
+    }
+
+    #[test]
+    fn intersection_keeps_the_overlap() {
+        assert_eq!(edges(&spheres(CsgOp::Intersection, 0.0, 1.0).ray_spans(&ray())), vec![(5.0, -1.0, 6.0, 1.0)]);
+    }
+
+    #[test]
+    fn difference_ends_at_the_subtracted_near_face() {
+        // La entrada de la esfera derecha cierra el tramo, con la normal invertida hacia fuera
+        assert_eq!(edges(&spheres(CsgOp::Difference, 0.0, 1.0).ray_spans(&ray())), vec![(4.0, -1.0, 5.0, 1.0)]);
+    }
+
+    #[test]
+    fn difference_starts_at_the_subtracted_far_face() {
+        // Al restar la esfera izquierda de la derecha, la cara trasera de la izquierda queda
+        // como superficie visible, mirando hacia el rayo
+        let csg = spheres(CsgOp::Difference, 1.0, 0.0);
+        assert_eq!(edges(&csg.ray_spans(&ray())), vec![(6.0, -1.0, 7.0, 1.0)]);
+        let hit = csg.ray_intersect(&ray());
+        assert_eq!((hit.distance, hit.normal), (6.0, Vec3::new(-1.0, 0.0, 0.0)));
+    }
+}
@@ -1,6 +1,6 @@
 use nalgebra_glm::Vec3;
 use crate::material::Material;
-use crate::texture::Texture;
+use crate::ray_intersect::{Intersect, Ray};
 
 #[derive(Debug, Clone)]
 pub struct Cube {
@@ -19,26 +19,76 @@ impl Cube {
     pub fn get_normal(&self, point: &Vec3) -> Vec3 {
         // Calcular la normal del cubo
         if (point.x - self.min.x).abs() < 1e-4 {
-            return Vec3::new(-1.0, 0.0, 0.0);
+            Vec3::new(-1.0, 0.0, 0.0)
         } else if (point.x - self.max.x).abs() < 1e-4 {
-            return Vec3::new(1.0, 0.0, 0.0);
+            Vec3::new(1.0, 0.0, 0.0)
         } else if (point.y - self.min.y).abs() < 1e-4 {
-            return Vec3::new(0.0, -1.0, 0.0);
+            Vec3::new(0.0, -1.0, 0.0)
         } else if (point.y - self.max.y).abs() < 1e-4 {
-            return Vec3::new(0.0, 1.0, 0.0);
+            Vec3::new(0.0, 1.0, 0.0)
         } else if (point.z - self.min.z).abs() < 1e-4 {
-            return Vec3::new(0.0, 0.0, -1.0);
+            Vec3::new(0.0, 0.0, -1.0)
         } else {
-            return Vec3::new(0.0, 0.0, 1.0);
+            Vec3::new(0.0, 0.0, 1.0)
         }
     }
 
-    // Calcula las coordenadas UV según la intersección
+    // Intersección con el material evaluado en el punto (textura de imagen o procedural)
+    pub fn surface_hit(&self, point: Vec3, normal: Vec3, distance: f32, ray: &Ray) -> Intersect {
+        let extent = self.max - self.min;
+        let uv_size = extent.x.max(extent.y).max(extent.z);
+        let cos_theta = normal.dot(&ray.direction.normalize());
+        let local = point - self.min;
+        let footprint = ray.footprint(distance * ray.direction.magnitude());
+        let material = self.material.at_surface(self.get_uv(&point), &local, uv_size, footprint, cos_theta);
+        Intersect::new(point, normal, distance, material)
+    }
+
+    pub fn get_face(&self, point: &Vec3) -> CubeFace {
+        CubeFace::from_normal(&self.get_normal(point))
+    }
+
+    // Calcula las coordenadas UV según la cara del cubo en la que cae el punto
     pub fn get_uv(&self, point: &Vec3) -> (f32, f32) {
-        // Calcula las coordenadas UV basadas en la posición del punto en la cara del cubo
-        // Este es un ejemplo básico; asegúrate de ajustar según el tamaño y orientación de tu cubo
-        let u = (point.x - self.min.x) / (self.max.x - self.min.x);
-        let v = (point.y - self.min.y) / (self.max.y - self.min.y);
-        (u, v)
+        let local = (point - self.min).component_div(&(self.max - self.min));
+        self.get_face(point).uv(&local)
+    }
+}
+
+// Las seis caras de un cubo alineado a los ejes, en el orden -x, +x, -y, +y, -z, +z
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CubeFace {
+    Left,
+    Right,
+    Bottom,
+    Top,
+    Back,
+    Front,
+}
+
+impl CubeFace {
+    pub fn from_normal(normal: &Vec3) -> Self {
+        let abs = normal.abs();
+        if abs.x >= abs.y && abs.x >= abs.z {
+            if normal.x < 0.0 { CubeFace::Left } else { CubeFace::Right }
+        } else if abs.y >= abs.z {
+            if normal.y < 0.0 { CubeFace::Bottom } else { CubeFace::Top }
+        } else if normal.z < 0.0 {
+            CubeFace::Back
+        } else {
+            CubeFace::Front
+        }
+    }
+
+    // UV dentro de la cara a partir de la posición local del punto (0..1 en cada eje).
+    // En las caras laterales v crece hacia abajo para que la imagen quede derecha.
+    pub fn uv(self, local: &Vec3) -> (f32, f32) {
+        match self {
+            CubeFace::Left => (local.z, 1.0 - local.y),
+            CubeFace::Right => (1.0 - local.z, 1.0 - local.y),
+            CubeFace::Back => (1.0 - local.x, 1.0 - local.y),
+            CubeFace::Front => (local.x, 1.0 - local.y),
+            CubeFace::Bottom | CubeFace::Top => (local.x, local.z),
+        }
     }
 }
\ No newline at end of file
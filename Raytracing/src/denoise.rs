@@ -0,0 +1,96 @@
+
+use nalgebra_glm::Vec3;
+use crate::aov::AovBuffers;
+use crate::framebuffer::Framebuffer;
+
+// Pesos del núcleo B3-spline de 5 muestras, el de la transformada à-trous
+const KERNEL: [f32; 5] = [1.0 / 16.0, 1.0 / 4.0, 3.0 / 8.0, 1.0 / 4.0, 1.0 / 16.0];
+
+// Filtro à-trous con bordes (Dammertz et al. 2010): el mismo núcleo 5x5 con huecos cada vez
+// más grandes, sin mezclar píxeles de distinta normal, profundidad o color. Se filtra la
+// iluminación (color dividido por el albedo) para que las texturas no se emborronen.
+#[derive(Debug, Clone, Copy)]
+pub struct Denoiser {
+    pub iterations: u32,     // Pasadas; el hueco se dobla en cada una
+    pub sigma_color: f32,    // Tolerancia a diferencias de iluminación, se reduce a la mitad por pasada
+    pub sigma_normal: f32,   // Exponente sobre el coseno entre normales
+    pub sigma_depth: f32,    // Tolerancia a diferencias de profundidad, relativa a la distancia
+}
+
+impl Default for Denoiser {
+    fn default() -> Self {
+        Denoiser { iterations: 4, sigma_color: 0.6, sigma_normal: 64.0, sigma_depth: 0.05 }
+    }
+}
+
+impl Denoiser {
+    // Filtra la radiancia del framebuffer en su sitio; sin pases auxiliares no hace nada
+    pub fn apply(&self, framebuffer: &mut Framebuffer) {
+        let Some(aovs) = &framebuffer.aovs else {
+            return;
+        };
+
+        // El albedo se limita por abajo: dividir por un canal casi negro dispararía el ruido
+        let modulation: Vec<Vec3> = aovs.pixels.iter().map(|p| p.albedo.map(|a| a.max(0.1))).collect();
+        let mut illumination: Vec<Vec3> = framebuffer
+            .radiance
+            .iter()
+            .zip(&modulation)
+            .map(|(radiance, albedo)| radiance.component_div(albedo))
+            .collect();
+
+        for iteration in 0..self.iterations {
+            illumination = self.pass(&illumination, aovs, 1 << iteration, self.sigma_color / (1 << iteration) as f32);
+        }
+
+        for ((radiance, light), albedo) in framebuffer.radiance.iter_mut().zip(&illumination).zip(&modulation) {
+            *radiance = light.component_mul(albedo);
+        }
+    }
+
+    fn pass(&self, input: &[Vec3], aovs: &AovBuffers, step: usize, sigma_color: f32) -> Vec<Vec3> {
+        let (width, height) = (aovs.width as isize, aovs.height as isize);
+        let mut output = input.to_vec();
+
+        for y in 0..height {
+            for x in 0..width {
+                let index = (y * width + x) as usize;
+                let center = &aovs.pixels[index];
+                // El cielo no tiene ruido ni datos de superficie
+                if center.object_id == 0 {
+                    continue;
+                }
+
+                let mut sum = Vec3::zeros();
+                let mut total = 0.0;
+                for (j, ky) in KERNEL.iter().enumerate() {
+                    for (i, kx) in KERNEL.iter().enumerate() {
+                        let sx = x + (i as isize - 2) * step as isize;
+                        let sy = y + (j as isize - 2) * step as isize;
+                        if sx < 0 || sy < 0 || sx >= width || sy >= height {
+                            continue;
+                        }
+                        let sample_index = (sy * width + sx) as usize;
+                        let sample = &aovs.pixels[sample_index];
+                        if sample.object_id == 0 {
+                            continue;
+                        }
+
+                        let w_normal = center.normal.dot(&sample.normal).max(0.0).powf(self.sigma_normal);
+                        let w_depth = (-(center.depth - sample.depth).abs() / (self.sigma_depth * center.depth * step as f32 + 1e-4)).exp();
+                        let difference = input[index] - input[sample_index];
+                        let w_color = (-difference.norm_squared() / (sigma_color * sigma_color + 1e-6)).exp();
+
+                        let weight = kx * ky * w_normal * w_depth * w_color;
+                        sum += input[sample_index] * weight;
+                        total += weight;
+                    }
+                }
+                if total > 0.0 {
+                    output[index] = sum / total;
+                }
+            }
+        }
+        output
+    }
+}
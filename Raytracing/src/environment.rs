@@ -0,0 +1,335 @@
+
+use nalgebra_glm::Vec3;
+use std::f32::consts::PI;
+use crate::exr::{self, ChannelData};
+
+// Resolución de las versiones reducidas del mapa para la irradiancia y los brillos prefiltrados
+const FILTER_WIDTH: usize = 64;
+const FILTER_HEIGHT: usize = 32;
+// Niveles de rugosidad prefiltrados, repartidos de 0 (espejo) a 1
+const ROUGHNESS_LEVELS: usize = 5;
+
+// Cómo ilumina el mapa de entorno las superficies difusas
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum IblMode {
+    // Rayos hacia el entorno elegidos según su luminancia, con sombras; más ruido, más exacto
+    Sampled,
+    // Irradiancia precalculada, sin sombras ni ruido, como la luz ambiente de Whitted
+    Prefiltered,
+}
+
+// Imagen equirrectangular de radiancia lineal que rodea la escena: el fondo de los rayos
+// perdidos y una fuente de luz más. La columna central mira hacia -z y la fila superior al cénit,
+// igual que la proyección equirrectangular de la cámara.
+pub struct EnvironmentMap {
+    pub width: usize,
+    pub height: usize,
+    pub pixels: Vec<Vec3>,
+    pub intensity: f32,  // Multiplica toda la radiancia del mapa
+    pub rotation: f32,   // Giro alrededor del eje vertical, en radianes
+    pub mode: IblMode,
+    pub samples: u32,    // Rayos por punto en `IblMode::Sampled`
+    row_cdf: Vec<f32>,        // Distribución acumulada de las filas
+    column_cdf: Vec<f32>,     // Distribución acumulada de las columnas dentro de cada fila
+    total_weight: f32,
+    irradiance: [Vec3; 9],    // Armónicos esféricos de la irradiancia
+    specular: Vec<Vec<Vec3>>, // Un mapa reducido por nivel de rugosidad, a partir del segundo
+}
+
+impl EnvironmentMap {
+    pub fn new(width: usize, height: usize, pixels: Vec<Vec3>) -> Self {
+        let mut map = EnvironmentMap {
+            width,
+            height,
+            pixels,
+            intensity: 1.0,
+            rotation: 0.0,
+            mode: IblMode::Prefiltered,
+            samples: 16,
+            row_cdf: Vec::new(),
+            column_cdf: Vec::new(),
+            total_weight: 0.0,
+            irradiance: [Vec3::zeros(); 9],
+            specular: Vec::new(),
+        };
+        map.build_distribution();
+        map.prefilter();
+        map
+    }
+
+    // Carga un `.hdr` de Radiance o un `.exr` según la extensión
+    pub fn load(path: &str) -> Self {
+        if path.to_lowercase().ends_with(".exr") {
+            let (width, height, channels) = exr::read(path).expect("Error al abrir el mapa de entorno");
+            let channel = |name: &str| {
+                channels.iter().find(|channel| channel.name == name || channel.name == format!("Y{}", name)).and_then(|channel| match &channel.data {
+                    ChannelData::Float(values, _) => Some(values.clone()),
+                    ChannelData::Uint(_) => None,
+                })
+            };
+            // Las imágenes en escala de grises solo tienen el canal Y
+            let gray = channel("Y");
+            let [r, g, b] = ["R", "G", "B"].map(|name| channel(name).or(gray.clone()).expect("El mapa de entorno no tiene canales R, G y B"));
+            let pixels = (0..width * height).map(|i| Vec3::new(r[i], g[i], b[i]).map(|c| if c.is_finite() { c.max(0.0) } else { 0.0 })).collect();
+            return EnvironmentMap::new(width, height, pixels);
+        }
+
+        let file = std::io::BufReader::new(std::fs::File::open(path).expect("Error al abrir el mapa de entorno"));
+        let decoder = image::codecs::hdr::HdrDecoder::new(file).expect("El mapa de entorno no es un archivo Radiance válido");
+        let metadata = decoder.metadata();
+        let pixels = decoder.read_image_hdr().expect("Error al leer el mapa de entorno");
+        let pixels = pixels.into_iter().map(|p| Vec3::new(p[0], p[1], p[2])).collect();
+        EnvironmentMap::new(metadata.width as usize, metadata.height as usize, pixels)
+    }
+
+    pub fn with_intensity(mut self, intensity: f32) -> Self {
+        self.intensity = intensity;
+        self
+    }
+
+    pub fn with_rotation(mut self, rotation: f32) -> Self {
+        self.rotation = rotation;
+        self
+    }
+
+    pub fn with_mode(mut self, mode: IblMode, samples: u32) -> Self {
+        self.mode = mode;
+        self.samples = samples.max(1);
+        self
+    }
+
+    // Radiancia que llega desde la dirección `direction`
+    pub fn radiance(&self, direction: &Vec3) -> Vec3 {
+        let (u, v) = self.to_uv(direction);
+        bilinear(&self.pixels, self.width, self.height, u, v) * self.intensity
+    }
+
+    // Irradiancia sobre una superficie con normal `normal`, sin tener en cuenta lo que la tapa
+    pub fn irradiance(&self, normal: &Vec3) -> Vec3 {
+        let n = self.local(normal);
+        let basis = sh_basis(&n);
+        // Convolución con el coseno: factores π, 2π/3 y π/4 por banda
+        let band = [PI, 2.0 * PI / 3.0, 2.0 * PI / 3.0, 2.0 * PI / 3.0, PI / 4.0, PI / 4.0, PI / 4.0, PI / 4.0, PI / 4.0];
+        let irradiance: Vec3 = (0..9).map(|i| self.irradiance[i] * (basis[i] * band[i])).sum();
+        irradiance.map(|c| c.max(0.0)) * self.intensity
+    }
+
+    // Radiancia reflejada en la dirección `direction` por una superficie de rugosidad
+    // `roughness`, ya promediada sobre el lóbulo especular
+    pub fn specular(&self, direction: &Vec3, roughness: f32) -> Vec3 {
+        let level = roughness.clamp(0.0, 1.0) * (ROUGHNESS_LEVELS - 1) as f32;
+        let (u, v) = self.to_uv(direction);
+        let lookup = |level: usize| match level {
+            0 => bilinear(&self.pixels, self.width, self.height, u, v),
+            _ => bilinear(&self.specular[level - 1], FILTER_WIDTH, FILTER_HEIGHT, u, v),
+        };
+        let (lower, t) = (level.floor() as usize, level.fract());
+        let upper = (lower + 1).min(ROUGHNESS_LEVELS - 1);
+        (lookup(lower) * (1.0 - t) + lookup(upper) * t) * self.intensity
+    }
+
+    // Elige una dirección con probabilidad proporcional a la luminancia del mapa a partir de
+    // dos números en 0..1; devuelve la dirección, su radiancia y la densidad de probabilidad
+    // por ángulo sólido
+    pub fn sample(&self, u1: f32, u2: f32) -> Option<(Vec3, Vec3, f32)> {
+        if self.total_weight <= 0.0 {
+            return None;
+        }
+        let row = self.row_cdf.partition_point(|&c| c < u1).min(self.height - 1);
+        let columns = &self.column_cdf[row * self.width..(row + 1) * self.width];
+        let column = columns.partition_point(|&c| c < u2).min(self.width - 1);
+
+        // Un punto dentro del píxel elegido, para no repetir siempre su centro: lo que sobra de
+        // cada número dentro de su intervalo se reparte de forma uniforme por el ángulo sólido
+        // del píxel (uniforme en la longitud y en el coseno de la latitud)
+        let fraction = |cdf: &[f32], i: usize, x: f32| {
+            let below = if i == 0 { 0.0 } else { cdf[i - 1] };
+            ((x - below) / (cdf[i] - below).max(1e-12)).clamp(0.0, 1.0)
+        };
+        let u = (column as f32 + fraction(columns, column, u2)) / self.width as f32;
+        let (cos_top, cos_bottom) = self.row_bounds(row);
+        let cos_theta = cos_top + (cos_bottom - cos_top) * fraction(&self.row_cdf, row, u1);
+        let v = cos_theta.clamp(-1.0, 1.0).acos() / PI;
+        let direction = self.world_direction(u, v);
+
+        let pdf = self.pdf(&direction);
+        if pdf <= 0.0 {
+            return None;
+        }
+        // El valor del propio píxel, constante como la densidad con la que se ha elegido
+        Some((direction, self.pixels[row * self.width + column] * self.intensity, pdf))
+    }
+
+    // Densidad de probabilidad por ángulo sólido de que `sample` elija la dirección
+    pub fn pdf(&self, direction: &Vec3) -> f32 {
+        let (u, v) = self.to_uv(direction);
+        let x = ((u * self.width as f32) as usize).min(self.width - 1);
+        let y = ((v * self.height as f32) as usize).min(self.height - 1);
+        let sin_theta = (PI * (y as f32 + 0.5) / self.height as f32).sin();
+        let (cos_top, cos_bottom) = self.row_bounds(y);
+        let solid_angle = 2.0 * PI / self.width as f32 * (cos_top - cos_bottom);
+        if solid_angle <= 0.0 || self.total_weight <= 0.0 {
+            return 0.0;
+        }
+        // Probabilidad del píxel (la misma que en `build_distribution`) repartida por su ángulo sólido
+        let weight = luminance(&self.pixels[y * self.width + x]) * sin_theta / self.total_weight;
+        weight / solid_angle
+    }
+
+    // Coseno de la latitud en los bordes superior e inferior de la fila `y`
+    fn row_bounds(&self, y: usize) -> (f32, f32) {
+        let theta = |y: usize| PI * y as f32 / self.height as f32;
+        (theta(y).cos(), theta(y + 1).cos())
+    }
+
+    // Dirección del mundo a coordenadas del mapa sin girar
+    fn local(&self, direction: &Vec3) -> Vec3 {
+        nalgebra_glm::rotate_y_vec3(direction, -self.rotation)
+    }
+
+    fn to_uv(&self, direction: &Vec3) -> (f32, f32) {
+        let d = self.local(direction).normalize();
+        let longitude = d.x.atan2(-d.z);
+        (longitude / (2.0 * PI) + 0.5, d.y.clamp(-1.0, 1.0).acos() / PI)
+    }
+
+    fn world_direction(&self, u: f32, v: f32) -> Vec3 {
+        nalgebra_glm::rotate_y_vec3(&uv_direction(u, v), self.rotation)
+    }
+
+    // Tablas acumuladas para elegir píxeles según luminancia por ángulo sólido
+    fn build_distribution(&mut self) {
+        let (width, height) = (self.width, self.height);
+        self.column_cdf = vec![0.0; width * height];
+        let mut row_weights = vec![0.0; height];
+        for (y, row_weight) in row_weights.iter_mut().enumerate() {
+            let sin_theta = (PI * (y as f32 + 0.5) / height as f32).sin();
+            let mut sum = 0.0;
+            for x in 0..width {
+                sum += luminance(&self.pixels[y * width + x]) * sin_theta;
+                self.column_cdf[y * width + x] = sum;
+            }
+            if sum > 0.0 {
+                self.column_cdf[y * width..(y + 1) * width].iter_mut().for_each(|c| *c /= sum);
+            }
+            *row_weight = sum;
+        }
+        self.total_weight = row_weights.iter().sum();
+        let mut sum = 0.0;
+        self.row_cdf = row_weights
+            .iter()
+            .map(|weight| {
+                sum += weight / self.total_weight.max(1e-12);
+                sum
+            })
+            .collect();
+    }
+
+    // Reduce el mapa y a partir de él calcula los armónicos de la irradiancia y los niveles
+    // especulares, convolucionando con un lóbulo de Phong equivalente a cada rugosidad
+    fn prefilter(&mut self) {
+        let small = downsample(&self.pixels, self.width, self.height, FILTER_WIDTH, FILTER_HEIGHT);
+        let texels: Vec<(Vec3, f32)> = (0..FILTER_WIDTH * FILTER_HEIGHT)
+            .map(|i| {
+                let (x, y) = (i % FILTER_WIDTH, i / FILTER_WIDTH);
+                let v = (y as f32 + 0.5) / FILTER_HEIGHT as f32;
+                let solid_angle = (2.0 * PI / FILTER_WIDTH as f32) * (PI / FILTER_HEIGHT as f32) * (PI * v).sin();
+                (uv_direction((x as f32 + 0.5) / FILTER_WIDTH as f32, v), solid_angle)
+            })
+            .collect();
+
+        self.irradiance = [Vec3::zeros(); 9];
+        for ((direction, solid_angle), radiance) in texels.iter().zip(&small) {
+            for (coefficient, basis) in self.irradiance.iter_mut().zip(sh_basis(direction)) {
+                *coefficient += radiance * (basis * solid_angle);
+            }
+        }
+
+        self.specular = (1..ROUGHNESS_LEVELS)
+            .map(|level| {
+                let roughness = level as f32 / (ROUGHNESS_LEVELS - 1) as f32;
+                let alpha = roughness * roughness;
+                let exponent = (2.0 / (alpha * alpha) - 2.0).max(0.0);
+                texels
+                    .iter()
+                    .map(|(center, _)| {
+                        let mut sum = Vec3::zeros();
+                        let mut total = 0.0;
+                        for ((direction, solid_angle), radiance) in texels.iter().zip(&small) {
+                            let cos = center.dot(direction);
+                            if cos > 0.0 {
+                                let weight = cos.powf(exponent) * solid_angle;
+                                sum += radiance * weight;
+                                total += weight;
+                            }
+                        }
+                        if total > 0.0 { sum / total } else { Vec3::zeros() }
+                    })
+                    .collect()
+            })
+            .collect();
+    }
+}
+
+fn uv_direction(u: f32, v: f32) -> Vec3 {
+    let longitude = (u - 0.5) * 2.0 * PI;
+    let theta = v * PI;
+    Vec3::new(theta.sin() * longitude.sin(), theta.cos(), -theta.sin() * longitude.cos())
+}
+
+fn luminance(color: &Vec3) -> f32 {
+    0.2126 * color.x + 0.7152 * color.y + 0.0722 * color.z
+}
+
+// Interpolación bilineal; en horizontal da la vuelta, en vertical se recorta
+fn bilinear(pixels: &[Vec3], width: usize, height: usize, u: f32, v: f32) -> Vec3 {
+    let x = u * width as f32 - 0.5;
+    let y = (v * height as f32 - 0.5).clamp(0.0, (height - 1) as f32);
+    let (x0, y0) = (x.floor(), y.floor());
+    let (tx, ty) = (x - x0, y - y0);
+    let column = |x: f32| (x as i64).rem_euclid(width as i64) as usize;
+    let (x0, x1) = (column(x0), column(x0 + 1.0));
+    let (y0, y1) = (y0 as usize, (y0 as usize + 1).min(height - 1));
+    let top = pixels[y0 * width + x0] * (1.0 - tx) + pixels[y0 * width + x1] * tx;
+    let bottom = pixels[y1 * width + x0] * (1.0 - tx) + pixels[y1 * width + x1] * tx;
+    top * (1.0 - ty) + bottom * ty
+}
+
+// Promedio por cajas; conserva la energía de los puntos muy brillantes como el sol
+fn downsample(pixels: &[Vec3], width: usize, height: usize, target_width: usize, target_height: usize) -> Vec<Vec3> {
+    let mut sums = vec![Vec3::zeros(); target_width * target_height];
+    let mut counts = vec![0u32; target_width * target_height];
+    for y in 0..height {
+        let ty = y * target_height / height;
+        for x in 0..width {
+            let index = ty * target_width + x * target_width / width;
+            sums[index] += pixels[y * width + x];
+            counts[index] += 1;
+        }
+    }
+    // Si el mapa es más pequeño que el destino, los huecos toman el píxel más cercano
+    (0..target_width * target_height)
+        .map(|i| match counts[i] {
+            0 => {
+                let (x, y) = (i % target_width, i / target_width);
+                pixels[(y * height / target_height) * width + x * width / target_width]
+            }
+            count => sums[i] / count as f32,
+        })
+        .collect()
+}
+
+// Base de armónicos esféricos reales hasta la banda 2
+fn sh_basis(d: &Vec3) -> [f32; 9] {
+    [
+        0.282095,
+        0.488603 * d.y,
+        0.488603 * d.z,
+        0.488603 * d.x,
+        1.092548 * d.x * d.y,
+        1.092548 * d.y * d.z,
+        0.315392 * (3.0 * d.z * d.z - 1.0),
+        1.092548 * d.x * d.z,
+        0.546274 * (d.x * d.x - d.y * d.y),
+    ]
+}
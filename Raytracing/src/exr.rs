@@ -0,0 +1,499 @@
+
+use std::fs::File;
+use std::io::{self, BufWriter, Write};
+
+// Compresiones que se saben leer; las demás (PIZ, PXR24, B44, DWA) dan error
+const NO_COMPRESSION: u8 = 0;
+const RLE_COMPRESSION: u8 = 1;
+const ZIPS_COMPRESSION: u8 = 2;
+const ZIP_COMPRESSION: u8 = 3;
+
+// Precisión de los canales de color: media precisión ocupa la mitad y basta para la imagen final
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Precision {
+    Half,
+    Float,
+}
+
+pub enum ChannelData {
+    Float(Vec<f32>, Precision),
+    Uint(Vec<u32>),  // Identificadores, sin pérdida
+}
+
+// Un canal de la imagen; las capas se nombran con prefijo, como `normal.X`
+pub struct Channel {
+    pub name: String,
+    pub data: ChannelData,
+}
+
+impl Channel {
+    pub fn float(name: &str, values: Vec<f32>, precision: Precision) -> Self {
+        Channel { name: name.to_string(), data: ChannelData::Float(values, precision) }
+    }
+
+    pub fn uint(name: &str, values: Vec<u32>) -> Self {
+        Channel { name: name.to_string(), data: ChannelData::Uint(values) }
+    }
+
+    // Tipo de píxel de OpenEXR: 0 UINT, 1 HALF, 2 FLOAT
+    fn pixel_type(&self) -> i32 {
+        match self.data {
+            ChannelData::Uint(_) => 0,
+            ChannelData::Float(_, Precision::Half) => 1,
+            ChannelData::Float(_, Precision::Float) => 2,
+        }
+    }
+
+    fn bytes_per_sample(&self) -> usize {
+        if self.pixel_type() == 1 { 2 } else { 4 }
+    }
+
+    fn write_row(&self, out: &mut Vec<u8>, start: usize, width: usize) {
+        match &self.data {
+            ChannelData::Uint(values) => values[start..start + width].iter().for_each(|v| out.extend_from_slice(&v.to_le_bytes())),
+            ChannelData::Float(values, Precision::Float) => values[start..start + width].iter().for_each(|v| out.extend_from_slice(&v.to_le_bytes())),
+            ChannelData::Float(values, Precision::Half) => values[start..start + width].iter().for_each(|v| out.extend_from_slice(&f32_to_half(*v).to_le_bytes())),
+        }
+    }
+}
+
+// Escribe una imagen OpenEXR de una sola parte, por líneas y sin compresión, con `width * height`
+// valores por canal en orden de filas
+pub fn write(path: &str, width: usize, height: usize, mut channels: Vec<Channel>) -> io::Result<()> {
+    // El formato exige los canales en orden alfabético
+    channels.sort_by(|a, b| a.name.cmp(&b.name));
+
+    let mut header = Vec::new();
+    header.extend_from_slice(&[0x76, 0x2f, 0x31, 0x01]);  // Número mágico
+    header.extend_from_slice(&2i32.to_le_bytes());        // Versión 2, una parte por líneas
+
+    let mut channel_list = Vec::new();
+    for channel in &channels {
+        channel_list.extend_from_slice(channel.name.as_bytes());
+        channel_list.push(0);
+        channel_list.extend_from_slice(&channel.pixel_type().to_le_bytes());
+        channel_list.extend_from_slice(&[0, 0, 0, 0]);  // pLinear y reservado
+        channel_list.extend_from_slice(&1i32.to_le_bytes());
+        channel_list.extend_from_slice(&1i32.to_le_bytes());
+    }
+    channel_list.push(0);
+
+    let window: Vec<u8> = [0, 0, width as i32 - 1, height as i32 - 1].iter().flat_map(|v| v.to_le_bytes()).collect();
+    attribute(&mut header, "channels", "chlist", &channel_list);
+    attribute(&mut header, "compression", "compression", &[NO_COMPRESSION]);
+    attribute(&mut header, "dataWindow", "box2i", &window);
+    attribute(&mut header, "displayWindow", "box2i", &window);
+    attribute(&mut header, "lineOrder", "lineOrder", &[0]);
+    attribute(&mut header, "pixelAspectRatio", "float", &1f32.to_le_bytes());
+    attribute(&mut header, "screenWindowCenter", "v2f", &[0; 8]);
+    attribute(&mut header, "screenWindowWidth", "float", &1f32.to_le_bytes());
+    header.push(0);
+
+    // Tabla de desplazamientos: dónde empieza cada línea desde el inicio del archivo
+    let row_bytes: usize = channels.iter().map(|channel| channel.bytes_per_sample() * width).sum();
+    let first_row = header.len() + 8 * height;
+    let mut out = BufWriter::new(File::create(path)?);
+    out.write_all(&header)?;
+    for y in 0..height {
+        out.write_all(&((first_row + y * (8 + row_bytes)) as u64).to_le_bytes())?;
+    }
+
+    let mut row = Vec::with_capacity(row_bytes);
+    for y in 0..height {
+        row.clear();
+        for channel in &channels {
+            channel.write_row(&mut row, y * width, width);
+        }
+        out.write_all(&(y as i32).to_le_bytes())?;
+        out.write_all(&(row.len() as i32).to_le_bytes())?;
+        out.write_all(&row)?;
+    }
+    out.flush()
+}
+
+// Lee una imagen OpenEXR de una sola parte por líneas, sin comprimir o con RLE o ZIP.
+// Devuelve el ancho, el alto y los canales, los de color siempre como `Precision::Float`.
+pub fn read(path: &str) -> io::Result<(usize, usize, Vec<Channel>)> {
+    let data = std::fs::read(path)?;
+    let invalid = |message: &str| io::Error::new(io::ErrorKind::InvalidData, format!("{}: {}", path, message));
+    if data.len() < 8 || data[..4] != [0x76, 0x2f, 0x31, 0x01] {
+        return Err(invalid("no es un archivo OpenEXR"));
+    }
+    // Los bits de imagen en mosaico, profunda o de partes múltiples no están soportados; el de
+    // nombres largos sí, porque los nombres se leen hasta el cero sin límite de longitud
+    if data[5] & 0x1A != 0 {
+        return Err(invalid("solo se leen imágenes de una parte por líneas"));
+    }
+
+    let mut reader = Reader { data: &data, position: 8 };
+    let mut channel_list = Vec::new();
+    let mut compression = NO_COMPRESSION;
+    let mut window = [0i32; 4];
+    loop {
+        let name = reader.string().ok_or_else(|| invalid("cabecera incompleta"))?;
+        if name.is_empty() {
+            break;
+        }
+        let _kind = reader.string().ok_or_else(|| invalid("cabecera incompleta"))?;
+        let size = reader.i32().ok_or_else(|| invalid("cabecera incompleta"))? as usize;
+        let value = reader.bytes(size).ok_or_else(|| invalid("cabecera incompleta"))?;
+        match name.as_str() {
+            "channels" => {
+                let mut list = Reader { data: value, position: 0 };
+                while let Some(channel) = list.string().filter(|channel| !channel.is_empty()) {
+                    let pixel_type = list.i32().ok_or_else(|| invalid("lista de canales incompleta"))?;
+                    list.bytes(12).ok_or_else(|| invalid("lista de canales incompleta"))?;
+                    channel_list.push((channel, pixel_type));
+                }
+            }
+            "compression" => compression = value.first().copied().unwrap_or(NO_COMPRESSION),
+            "dataWindow" => {
+                let mut bounds = Reader { data: value, position: 0 };
+                for bound in window.iter_mut() {
+                    *bound = bounds.i32().ok_or_else(|| invalid("dataWindow incompleta"))?;
+                }
+            }
+            _ => {}
+        }
+    }
+
+    let lines_per_block = match compression {
+        NO_COMPRESSION | RLE_COMPRESSION | ZIPS_COMPRESSION => 1,
+        ZIP_COMPRESSION => 16,
+        _ => return Err(invalid("compresión no soportada")),
+    };
+    let width = (window[2] - window[0] + 1).max(0) as usize;
+    let height = (window[3] - window[1] + 1).max(0) as usize;
+    let sample_size = |pixel_type: i32| if pixel_type == 1 { 2 } else { 4 };
+    let row_bytes: usize = channel_list.iter().map(|(_, pixel_type)| sample_size(*pixel_type) * width).sum();
+
+    let mut values: Vec<Vec<f32>> = vec![vec![0.0; width * height]; channel_list.len()];
+    let mut ids: Vec<Vec<u32>> = vec![Vec::new(); channel_list.len()];
+    for (i, (_, pixel_type)) in channel_list.iter().enumerate() {
+        if *pixel_type == 0 {
+            ids[i] = vec![0; width * height];
+        }
+    }
+
+    let blocks = height.div_ceil(lines_per_block);
+    for block in 0..blocks {
+        let offset = Reader { data: &data, position: reader.position + 8 * block }.u64().ok_or_else(|| invalid("tabla de desplazamientos incompleta"))?;
+        let mut chunk = Reader { data: &data, position: offset as usize };
+        let first_line = chunk.i32().ok_or_else(|| invalid("bloque incompleto"))? - window[1];
+        let size = chunk.i32().ok_or_else(|| invalid("bloque incompleto"))? as usize;
+        let packed = chunk.bytes(size).ok_or_else(|| invalid("bloque incompleto"))?;
+
+        let lines = lines_per_block.min(height.saturating_sub(first_line.max(0) as usize));
+        let expected = row_bytes * lines;
+        let pixels = if size >= expected {
+            packed.to_vec()
+        } else if compression == RLE_COMPRESSION {
+            reorder(&unpack_rle(packed).ok_or_else(|| invalid("datos RLE truncados"))?)
+        } else {
+            reorder(&miniz_oxide::inflate::decompress_to_vec_zlib(packed).map_err(|_| invalid("datos ZIP dañados"))?)
+        };
+        if pixels.len() < expected {
+            return Err(invalid("bloque más corto de lo esperado"));
+        }
+
+        // Dentro del bloque, cada línea lleva sus canales uno detrás de otro
+        let mut position = 0;
+        for line in 0..lines {
+            let start = (first_line as usize + line) * width;
+            for (i, (_, pixel_type)) in channel_list.iter().enumerate() {
+                for x in 0..width {
+                    let bytes = &pixels[position..position + sample_size(*pixel_type)];
+                    position += bytes.len();
+                    match pixel_type {
+                        0 => ids[i][start + x] = u32::from_le_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]),
+                        1 => values[i][start + x] = half_to_f32(u16::from_le_bytes([bytes[0], bytes[1]])),
+                        _ => values[i][start + x] = f32::from_le_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]),
+                    }
+                }
+            }
+        }
+    }
+
+    let channels = channel_list
+        .into_iter()
+        .zip(values.into_iter().zip(ids))
+        .map(|((name, pixel_type), (values, ids))| {
+            if pixel_type == 0 { Channel::uint(&name, ids) } else { Channel::float(&name, values, Precision::Float) }
+        })
+        .collect();
+    Ok((width, height, channels))
+}
+
+// Lectura secuencial de la cabecera y los bloques, en little endian
+struct Reader<'a> {
+    data: &'a [u8],
+    position: usize,
+}
+
+impl<'a> Reader<'a> {
+    fn bytes(&mut self, count: usize) -> Option<&'a [u8]> {
+        let bytes = self.data.get(self.position..self.position + count)?;
+        self.position += count;
+        Some(bytes)
+    }
+
+    fn i32(&mut self) -> Option<i32> {
+        self.bytes(4).map(|b| i32::from_le_bytes([b[0], b[1], b[2], b[3]]))
+    }
+
+    fn u64(&mut self) -> Option<u64> {
+        let mut value = [0; 8];
+        value.copy_from_slice(self.bytes(8)?);
+        Some(u64::from_le_bytes(value))
+    }
+
+    fn string(&mut self) -> Option<String> {
+        let length = self.data.get(self.position..)?.iter().position(|&b| b == 0)?;
+        let text = String::from_utf8_lossy(self.bytes(length)?).into_owned();
+        self.position += 1;
+        Some(text)
+    }
+}
+
+// RLE de OpenEXR: un contador negativo copia ese número de bytes tal cual, uno positivo
+// repite el byte siguiente contador + 1 veces. `None` si los datos se cortan a medias.
+fn unpack_rle(packed: &[u8]) -> Option<Vec<u8>> {
+    let mut out = Vec::new();
+    let mut i = 0;
+    while i < packed.len() {
+        let count = packed[i] as i8;
+        i += 1;
+        if count < 0 {
+            let end = i + (-(count as i32)) as usize;
+            out.extend_from_slice(packed.get(i..end)?);
+            i = end;
+        } else {
+            out.extend(std::iter::repeat_n(*packed.get(i)?, count as usize + 1));
+            i += 1;
+        }
+    }
+    Some(out)
+}
+
+// Deshace el predictor por diferencias y el reparto de bytes pares e impares que RLE y ZIP
+// aplican antes de comprimir
+fn reorder(packed: &[u8]) -> Vec<u8> {
+    let mut predicted = packed.to_vec();
+    for i in 1..predicted.len() {
+        predicted[i] = predicted[i - 1].wrapping_add(predicted[i]).wrapping_sub(128);
+    }
+    let half = predicted.len().div_ceil(2);
+    let mut out = Vec::with_capacity(predicted.len());
+    for i in 0..half {
+        out.push(predicted[i]);
+        if let Some(&byte) = predicted.get(half + i) {
+            out.push(byte);
+        }
+    }
+    out
+}
+
+fn attribute(header: &mut Vec<u8>, name: &str, kind: &str, value: &[u8]) {
+    header.extend_from_slice(name.as_bytes());
+    header.push(0);
+    header.extend_from_slice(kind.as_bytes());
+    header.push(0);
+    header.extend_from_slice(&(value.len() as i32).to_le_bytes());
+    header.extend_from_slice(value);
+}
+
+// Conversión a flotante de 16 bits (IEEE 754 binary16), redondeando al más cercano
+fn f32_to_half(value: f32) -> u16 {
+    let bits = value.to_bits();
+    let sign = ((bits >> 16) & 0x8000) as u16;
+    let exponent = ((bits >> 23) & 0xFF) as i32;
+    let mantissa = bits & 0x7F_FFFF;
+
+    if exponent == 0xFF {
+        // Infinito o NaN
+        return sign | 0x7C00 | if mantissa != 0 { 0x200 } else { 0 };
+    }
+    let exponent = exponent - 127 + 15;
+    if exponent >= 0x1F {
+        return sign | 0x7C00;  // Demasiado grande: infinito
+    }
+    if exponent <= 0 {
+        // Subnormal o cero
+        if exponent < -10 {
+            return sign;
+        }
+        let mantissa = mantissa | 0x80_0000;
+        let shift = (14 - exponent) as u32;
+        let half = (mantissa >> shift) as u16;
+        let round = ((mantissa >> (shift - 1)) & 1) as u16;
+        return sign | (half + round);
+    }
+    let half = sign | ((exponent as u16) << 10) | (mantissa >> 13) as u16;
+    // El redondeo puede desbordar la mantisa hacia el exponente, que es justo lo correcto
+    half + ((mantissa >> 12) & 1) as u16
+}
+
+fn half_to_f32(half: u16) -> f32 {
+    let sign = if half & 0x8000 != 0 { -1.0 } else { 1.0 };
+    let exponent = ((half >> 10) & 0x1F) as i32;
+    let mantissa = (half & 0x3FF) as f32;
+    match exponent {
+        0 => sign * mantissa * 2f32.powi(-24),
+        0x1F if mantissa == 0.0 => sign * f32::INFINITY,
+        0x1F => f32::NAN,
+        _ => sign * (1.0 + mantissa / 1024.0) * 2f32.powi(exponent - 15),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn temp_path(name: &str) -> String {
+        std::env::temp_dir().join(format!("raytracing_{}_{}.exr", name, std::process::id())).to_string_lossy().into_owned()
+    }
+
+    fn float_values(channel: &Channel) -> &[f32] {
+        match &channel.data {
+            ChannelData::Float(values, _) => values,
+            ChannelData::Uint(_) => panic!("{} no es un canal de color", channel.name),
+        }
+    }
+
+    #[test]
+    fn half_conversion_rounds_and_keeps_subnormals() {
+        assert_eq!(f32_to_half(0.0), 0x0000);
+        assert_eq!(f32_to_half(-0.0), 0x8000);
+        assert_eq!(f32_to_half(1.0), 0x3C00);
+        assert_eq!(f32_to_half(-2.0), 0xC000);
+        assert_eq!(f32_to_half(0.1), 0x2E66);
+        assert_eq!(f32_to_half(65504.0), 0x7BFF);
+        assert_eq!(f32_to_half(1e6), 0x7C00);
+        assert_eq!(f32_to_half(f32::INFINITY), 0x7C00);
+        assert!(half_to_f32(f32_to_half(f32::NAN)).is_nan());
+
+        // El menor normal y los subnormales, con el redondeo al más cercano
+        assert_eq!(f32_to_half(2f32.powi(-14)), 0x0400);
+        assert_eq!(f32_to_half(2f32.powi(-15)), 0x0200);
+        assert_eq!(f32_to_half(2f32.powi(-24)), 0x0001);
+        assert_eq!(f32_to_half(3.0 * 2f32.powi(-24)), 0x0003);
+        assert_eq!(f32_to_half(0.75 * 2f32.powi(-24)), 0x0001);
+        assert_eq!(f32_to_half(0.25 * 2f32.powi(-24)), 0x0000);
+        // La mantisa redondeada hacia arriba pasa al exponente siguiente
+        assert_eq!(f32_to_half(2.0 - 2f32.powi(-12)), 0x4000);
+
+        for half in [0x0001, 0x03FF, 0x0400, 0x3555, 0x3C00, 0x7BFF, 0xC000, 0x8001] {
+            assert_eq!(f32_to_half(half_to_f32(half)), half);
+        }
+    }
+
+    #[test]
+    fn write_read_round_trip() {
+        let (width, height) = (3, 2);
+        let color = vec![0.0, 1.0, -2.5, 0.1, 65504.0, 2f32.powi(-24)];
+        let depth = vec![0.0, 1.0 / 3.0, 1e-30, 123456.79, f32::INFINITY, -7.25];
+        let ids = vec![0, 1, 2, u32::MAX, 42, 7];
+        let path = temp_path("round_trip");
+        write(&path, width, height, vec![
+            Channel::float("R", color.clone(), Precision::Half),
+            Channel::uint("objectId", ids.clone()),
+            Channel::float("depth.Z", depth.clone(), Precision::Float),
+            Channel::float("B", color.clone(), Precision::Half),
+        ])
+        .unwrap();
+        let (read_width, read_height, channels) = read(&path).unwrap();
+        std::fs::remove_file(&path).unwrap();
+
+        assert_eq!((read_width, read_height), (width, height));
+        // Los canales salen en orden alfabético, como los escribe `write`
+        let names: Vec<&str> = channels.iter().map(|channel| channel.name.as_str()).collect();
+        assert_eq!(names, ["B", "R", "depth.Z", "objectId"]);
+
+        let expected_color: Vec<f32> = color.iter().map(|&v| half_to_f32(f32_to_half(v))).collect();
+        assert_eq!(float_values(&channels[0]), expected_color);
+        assert_eq!(float_values(&channels[1]), expected_color);
+        assert_eq!(expected_color[3], 0.099975586);  // 0.1 en media precisión
+        assert_eq!(float_values(&channels[2]), depth);
+        match &channels[3].data {
+            ChannelData::Uint(values) => assert_eq!(*values, ids),
+            ChannelData::Float(..) => panic!("objectId debería ser UINT"),
+        }
+    }
+
+    // Predictor y reparto de bytes de una línea con los HALF 1.0 y 2.0 (bytes 00 3C 00 40)
+    const PREDICTED: [u8; 4] = [0x00, 0x80, 0xBC, 0x84];
+
+    #[test]
+    fn rle_and_reorder_decode_known_bytes() {
+        assert_eq!(unpack_rle(&[0x03, 0x7F, 0xFE, 0x01, 0x02]).unwrap(), [0x7F, 0x7F, 0x7F, 0x7F, 0x01, 0x02]);
+        assert_eq!(unpack_rle(&[0xFC, 0x00, 0x80, 0xBC, 0x84]).unwrap(), PREDICTED);
+        // Un contador sin el byte que repite o una copia más larga que los datos restantes
+        assert_eq!(unpack_rle(&[0x03, 0x7F, 0x02]), None);
+        assert_eq!(unpack_rle(&[0xFC, 0x00, 0x80]), None);
+        assert_eq!(reorder(&PREDICTED), [0x00, 0x3C, 0x00, 0x40]);
+        // Con un número impar de bytes la primera mitad lleva uno más
+        assert_eq!(reorder(&[0x01, 0x81, 0x81]), [0x01, 0x03, 0x02]);
+    }
+
+    // Archivo de una línea de `width` HALF a 1.0 en el canal Y, con el bloque de píxeles
+    // sustituido por `block` y la compresión indicada en la cabecera
+    fn compressed_file(name: &str, width: usize, compression: u8, block: &[u8]) -> String {
+        let path = temp_path(name);
+        write(&path, width, 1, vec![Channel::float("Y", vec![1.0; width], Precision::Half)]).unwrap();
+        let mut data = std::fs::read(&path).unwrap();
+
+        let key = b"compression\0compression\0";
+        let at = data.windows(key.len()).position(|window| window == key).unwrap() + key.len() + 4;
+        data[at] = compression;
+        // El único bloque va al final: número de línea, tamaño y datos
+        data.truncate(data.len() - 8 - 2 * width);
+        data.extend_from_slice(&0i32.to_le_bytes());
+        data.extend_from_slice(&(block.len() as i32).to_le_bytes());
+        data.extend_from_slice(block);
+        std::fs::write(&path, data).unwrap();
+        path
+    }
+
+    #[test]
+    fn read_decodes_rle_and_zip_blocks() {
+        // 64 HALF a 1.0: los bytes bajos (00) y luego los altos (3C), tras el predictor
+        // un 00, 63 veces 80, un BC y otras 63 veces 80
+        let width = 64;
+        let rle = [0x00, 0x00, 0x3E, 0x80, 0x00, 0xBC, 0x3E, 0x80];
+        let mut predicted = vec![0x00];
+        predicted.extend([0x80; 63]);
+        predicted.push(0xBC);
+        predicted.extend([0x80; 63]);
+        let zip = miniz_oxide::deflate::compress_to_vec_zlib(&predicted, 6);
+        assert!(zip.len() < 2 * width);
+
+        for (name, compression, block) in [("rle", RLE_COMPRESSION, &rle[..]), ("zips", ZIPS_COMPRESSION, &zip), ("zip", ZIP_COMPRESSION, &zip)] {
+            let path = compressed_file(name, width, compression, block);
+            let result = read(&path);
+            std::fs::remove_file(&path).unwrap();
+            let (read_width, read_height, channels) = result.unwrap();
+            assert_eq!((read_width, read_height), (width, 1), "{}", name);
+            assert_eq!(float_values(&channels[0]), vec![1.0; width], "{}", name);
+        }
+    }
+
+    #[test]
+    fn read_accepts_long_names_and_rejects_tiled_files() {
+        let path = temp_path("flags");
+        write(&path, 2, 1, vec![Channel::float("Y", vec![1.0, 2.0], Precision::Half)]).unwrap();
+        let mut data = std::fs::read(&path).unwrap();
+
+        // Bit de nombres largos (0x400 en la versión)
+        data[5] |= 0x04;
+        std::fs::write(&path, &data).unwrap();
+        let long_names = read(&path);
+        // Bit de imagen en mosaico (0x200)
+        data[5] |= 0x02;
+        std::fs::write(&path, &data).unwrap();
+        let tiled = read(&path);
+        std::fs::remove_file(&path).unwrap();
+
+        assert_eq!(float_values(&long_names.unwrap().2[0]), [1.0, 2.0]);
+        assert!(tiled.is_err_and(|error| error.kind() == io::ErrorKind::InvalidData));
+    }
+}
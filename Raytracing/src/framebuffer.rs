@@ -1,8 +1,14 @@
+use nalgebra_glm::Vec3;
+use crate::aov::AovBuffers;
+use crate::color::Color;
+use crate::exr::{self, Channel, Precision};
 
 pub struct Framebuffer {
     pub width: usize,
     pub height: usize,
     pub buffer: Vec<u32>,
+    pub radiance: Vec<Vec3>,       // Radiancia lineal sin recortar; `buffer` sale de aquí con `tone_map`
+    pub aovs: Option<AovBuffers>,  // Pases auxiliares; solo se rellenan si están activados
     background_color: u32,
     current_color: u32,
 }
@@ -13,6 +19,8 @@ impl Framebuffer {
             width,
             height,
             buffer: vec![0; width * height],
+            radiance: vec![Vec3::zeros(); width * height],
+            aovs: None,
             background_color: 0x000000,
             current_color: 0xFFFFFF
         }
@@ -37,4 +45,56 @@ impl Framebuffer {
     pub fn set_current_color(&mut self, color: u32) {
         self.current_color = color;
     }
+
+    pub fn set_radiance(&mut self, x: usize, y: usize, radiance: Vec3) {
+        if x < self.width && y < self.height {
+            self.radiance[y * self.width + x] = radiance;
+        }
+    }
+
+    // Pasa la radiancia a los colores de 8 bits de la pantalla: el operador fílmico ACES comprime
+    // lo que pasa de 1 en lugar de recortarlo, y el resultado se codifica en sRGB
+    pub fn tone_map(&mut self) {
+        for (pixel, radiance) in self.buffer.iter_mut().zip(&self.radiance) {
+            *pixel = Color::from_linear(&radiance.map(aces)).to_hex();
+        }
+    }
+
+    pub fn enable_aovs(&mut self) -> &mut AovBuffers {
+        self.aovs.get_or_insert_with(|| AovBuffers::new(self.width, self.height))
+    }
+
+    // Guarda la imagen de 8 bits ya recortada; el formato sale de la extensión de `path`
+    pub fn save(&self, path: &str) -> image::ImageResult<()> {
+        let mut image = image::RgbImage::new(self.width as u32, self.height as u32);
+        for (i, pixel) in self.buffer.iter().enumerate() {
+            let rgb = [(pixel >> 16) as u8, (pixel >> 8) as u8, *pixel as u8];
+            image.put_pixel((i % self.width) as u32, (i / self.width) as u32, image::Rgb(rgb));
+        }
+        image.save(path)
+    }
+
+    // Radiance `.hdr`: la radiancia lineal en RGBE, sin recortar los brillos
+    pub fn save_hdr(&self, path: &str) -> image::ImageResult<()> {
+        let pixels: Vec<image::Rgb<f32>> = self.radiance.iter().map(|r| image::Rgb([r.x.max(0.0), r.y.max(0.0), r.z.max(0.0)])).collect();
+        let file = std::io::BufWriter::new(std::fs::File::create(path)?);
+        image::codecs::hdr::HdrEncoder::new(file).encode(&pixels, self.width, self.height)
+    }
+
+    // OpenEXR con la radiancia lineal en R, G y B y, si están activados, los pases auxiliares como capas
+    pub fn save_exr(&self, path: &str, precision: Precision) -> std::io::Result<()> {
+        let channel = |name: &str, component: fn(&Vec3) -> f32| Channel::float(name, self.radiance.iter().map(component).collect(), precision);
+        let mut channels = vec![channel("R", |r| r.x), channel("G", |r| r.y), channel("B", |r| r.z)];
+        if let Some(aovs) = &self.aovs {
+            channels.extend(aovs.exr_channels(precision));
+        }
+        exr::write(path, self.width, self.height, channels)
+    }
+}
+
+// Aproximación de Narkowicz a la curva fílmica ACES: lineal en las sombras y con un hombro
+// que lleva los brillos hacia 1 sin llegar a recortarlos
+fn aces(x: f32) -> f32 {
+    let x = x.max(0.0);
+    ((x * (2.51 * x + 0.03)) / (x * (2.43 * x + 0.59) + 0.14)).clamp(0.0, 1.0)
 }
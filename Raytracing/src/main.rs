@@ -8,106 +8,628 @@ mod light;
 mod material;
 mod cube;  // New cube module
 mod texture; // New texture module
+mod csg;
+mod procedural;
+mod sdf;
+mod noise;
+mod terrain;
+mod world;
+mod block;
+mod brdf;
+mod atmosphere;
+mod sky;
+mod clock;
+mod animation;
+mod motion;
+mod aov;
+mod denoise;
+mod exr;
+mod environment;
+mod occlusion;
+mod postprocess;
+mod sampling;
+mod math;
 
-use minifb::{ Window, WindowOptions, Key };
-use nalgebra_glm::{Vec3, normalize};
-use std::time::Duration;
+use minifb::{ Window, WindowOptions, Key, KeyRepeat, MouseButton, MouseMode };
+use nalgebra_glm::Vec3;
+use std::time::{Duration, Instant};
 use std::f32::consts::PI;
 
 use crate::color::Color;
-use crate::ray_intersect::{Intersect, RayIntersect};
+use crate::ray_intersect::{Intersect, Ray, RayIntersect, Span};
 use crate::sphere::Sphere;
 use crate::cube::Cube;  // New cube import
 use crate::framebuffer::Framebuffer;
-use crate::camera::Camera;
+use crate::camera::{Bokeh, Camera, FovAxis, Projection, Shutter, StereoLayout, StereoRig};
 use crate::light::Light;
-use crate::material::Material;
-use crate::texture::Texture;  // New texture import
+use crate::material::{Dielectric, Material, Pbr};
+use crate::texture::{Filter, Texture, WrapMode};  // New texture import
+use crate::csg::Csg;
+use crate::procedural::{Pattern, PatternSpace, ProceduralTexture};
+use crate::sdf::{Sdf, SdfObject};
+use crate::noise::Perlin;
+use crate::terrain::Heightmap;
+use crate::world::{Block, BlockGrid, WorldGenerator};
+use crate::block::{BlockCube, BlockRegistry};
+use crate::atmosphere::{Atmosphere, Fog, ScatteringVolume};
+use crate::sky::Sky;
+use crate::clock::Clock;
+use crate::animation::{CameraPath, Easing, Interpolation};
+use crate::motion::{Motion, Moving};
+use crate::aov::{AovSample, Pass};
+use crate::denoise::Denoiser;
+use crate::exr::Precision;
+use crate::environment::{EnvironmentMap, IblMode};
+use crate::occlusion::{AmbientOcclusion, OcclusionMode};
+use crate::postprocess::PostProcess;
+use std::rc::Rc;
 
 fn reflect(incident: &Vec3, normal: &Vec3) -> Vec3 {
     incident - 2.0 * incident.dot(normal) * normal
 }
 
+// Dirección refractada (ley de Snell) con `eta` = n1 / n2 y la normal del lado del rayo
+// incidente; `None` si hay reflexión interna total
+fn refract(incident: &Vec3, normal: &Vec3, eta: f32) -> Option<Vec3> {
+    let cos_i = -incident.dot(normal);
+    let sin_t2 = eta * eta * (1.0 - cos_i * cos_i);
+    if sin_t2 >= 1.0 {
+        return None;
+    }
+    Some(incident * eta + normal * (eta * cos_i - (1.0 - sin_t2).sqrt()))
+}
+
 pub enum Object {
     Sphere(Sphere),
     Cube(Cube),
+    Csg(Csg),
+    Sdf(SdfObject),
+    Terrain(Box<Heightmap>),  // En una caja: guarda dos materiales y la malla
+    World(BlockGrid),
+    Block(BlockCube),
+    Moving(Moving),
 }
 
 impl RayIntersect for Object {
-    fn ray_intersect(&self, ray_origin: &Vec3, ray_direction: &Vec3) -> Intersect {
+    fn ray_intersect(&self, ray: &Ray) -> Intersect {
         match self {
-            Object::Sphere(sphere) => sphere.ray_intersect(ray_origin, ray_direction),
-            Object::Cube(cube) => cube.ray_intersect(ray_origin, ray_direction),
+            Object::Sphere(sphere) => sphere.ray_intersect(ray),
+            Object::Cube(cube) => cube.ray_intersect(ray),
+            Object::Csg(csg) => csg.ray_intersect(ray),
+            Object::Sdf(sdf) => sdf.ray_intersect(ray),
+            Object::Terrain(terrain) => terrain.ray_intersect(ray),
+            Object::World(world) => world.ray_intersect(ray),
+            Object::Block(block) => block.ray_intersect(ray),
+            Object::Moving(moving) => moving.ray_intersect(ray),
+        }
+    }
+
+    fn ray_spans(&self, ray: &Ray) -> Vec<Span> {
+        match self {
+            Object::Sphere(sphere) => sphere.ray_spans(ray),
+            Object::Cube(cube) => cube.ray_spans(ray),
+            Object::Csg(csg) => csg.ray_spans(ray),
+            Object::Sdf(sdf) => sdf.ray_spans(ray),
+            Object::Terrain(terrain) => terrain.ray_spans(ray),
+            Object::World(world) => world.ray_spans(ray),
+            Object::Block(block) => block.ray_spans(ray),
+            Object::Moving(moving) => moving.ray_spans(ray),
         }
     }
 }
 
-fn cast_ray(ray_origin: &Vec3, ray_direction: &Vec3, objects: &[Object], lights: &[Light]) -> Color {
-    let mut intersect = Intersect::empty();
-    let mut zbuffer = f32::INFINITY;
+// Todo lo que necesita un rayo para sombrearse: los objetos, las luces, el cielo
+// para los rayos perdidos (o el mapa de entorno, que además ilumina), la luz ambiente con
+// su oclusión, el medio entre la cámara y las superficies y el instante del cuadro
+// (segundos), que mueve los objetos animados
+pub struct Scene<'a> {
+    pub objects: &'a [Object],
+    pub lights: &'a [Light],
+    pub sky: &'a Sky,
+    pub environment: Option<&'a EnvironmentMap>,
+    pub occlusion: Option<&'a AmbientOcclusion>,
+    pub atmosphere: &'a Atmosphere,
+    pub time: f32,
+}
+
+// Factor de sombra: 0.0 si algún objeto tapa la luz, 1.0 si la ve directamente.
+// Los rayos de sombra también atraviesan los texels recortados por alfa, y los medios
+// dieléctricos dejan pasar la luz atenuada según el espesor que recorre dentro de ellos.
+fn cast_shadow(intersect: &Intersect, light: &Light, time: f32, objects: &[Object]) -> f32 {
+    let light_dir = (light.position - intersect.point).normalize();
+    let offset = intersect.normal * 1e-3 * intersect.normal.dot(&light_dir).signum();
+    light_visibility(&(intersect.point + offset), light, time, objects)
+}
+
+// Fracción de la luz que llega sin obstáculos desde `light` hasta el punto `shadow_origin`
+// en el instante `time`
+fn light_visibility(shadow_origin: &Vec3, light: &Light, time: f32, objects: &[Object]) -> f32 {
+    let light_vector = light.position - shadow_origin;
+    let light_distance = light_vector.magnitude();
+    let shadow_ray = Ray::new(*shadow_origin, light_vector / light_distance, time);
+    let mut shadow = 1.0;
 
     for object in objects {
-        let tmp = object.ray_intersect(ray_origin, ray_direction);
-        if tmp.is_intersecting && tmp.distance < zbuffer {
-            zbuffer = tmp.distance;
-            intersect = tmp;
+        let hit = object.ray_intersect(&shadow_ray);
+        if hit.is_intersecting && hit.distance > 1e-4 && hit.distance < light_distance {
+            let Some(medium) = hit.material.dielectric else {
+                return 0.0;
+            };
+            let thickness: f32 = object
+                .ray_spans(&shadow_ray)
+                .iter()
+                .map(|span| (span.exit.distance.min(light_distance) - span.enter.distance.max(0.0)).max(0.0))
+                .sum();
+            shadow *= medium.transmittance(thickness).mean();
+        }
+    }
+
+    shadow
+}
+
+// Rebotes máximos de reflexión especular en materiales PBR
+const MAX_DEPTH: u32 = 3;
+
+// Sombreado físico: Cook-Torrance GGX por cada luz, emisión y un rayo reflejado
+// ponderado con Fresnel
+fn shade_pbr(intersect: &Intersect, pbr: &Pbr, object: &Object, ray: &Ray, scene: &Scene, depth: u32) -> (Vec3, Vec3) {
+    let base_color = intersect.material.diffuse.to_linear();
+    let view_dir = (ray.origin - intersect.point).normalize();
+    let normal = intersect.normal;
+
+    let direct = direct_pbr(intersect, pbr, ray, scene);
+    let mut radiance = direct;
+
+    // Reflexión del entorno: las superficies rugosas dispersan el lóbulo, así que el
+    // rayo especular único se atenúa con la rugosidad en lugar de verse como un espejo
+    let gloss = (1.0 - pbr.roughness).powi(2);
+    let n_dot_v = normal.dot(&view_dir).max(0.0);
+    let f0 = brdf::base_reflectance(&base_color, pbr.metallic);
+    let fresnel = brdf::fresnel_schlick_roughness(n_dot_v, &f0, pbr.roughness);
+    let reflect_dir = reflect(&-view_dir, &normal).normalize();
+    let traced = depth < MAX_DEPTH && gloss > 0.01;
+    if traced {
+        let reflect_origin = intersect.point + normal * 1e-3;
+        let reflected = cast_ray(&ray.spawn(reflect_origin, reflect_dir), scene, depth + 1);
+        radiance += reflected.component_mul(&fresnel) * gloss;
+    }
+
+    // Luz ambiente y del entorno en la parte difusa; con mapa de entorno, además, la parte
+    // del lóbulo especular que no cubre el rayo
+    let kd = (Vec3::new(1.0, 1.0, 1.0) - fresnel) * (1.0 - pbr.metallic);
+    radiance += kd.component_mul(&base_color).component_mul(&ambient_diffuse(intersect, object, scene, ray.time, depth));
+    if let Some(environment) = scene.environment {
+        let specular_weight = if traced { 1.0 - gloss } else { 1.0 };
+        radiance += environment.specular(&reflect_dir, pbr.roughness).component_mul(&fresnel) * specular_weight;
+    }
+
+    (radiance, direct)
+}
+
+// Emisión y luz de cada fuente con Cook-Torrance, sin rebotes
+fn direct_pbr(intersect: &Intersect, pbr: &Pbr, ray: &Ray, scene: &Scene) -> Vec3 {
+    let base_color = intersect.material.diffuse.to_linear();
+    let view_dir = (ray.origin - intersect.point).normalize();
+    let mut radiance = pbr.emissive.to_linear() * pbr.emissive_strength;
+
+    for light in scene.lights.iter().filter(|light| light.intensity > 0.0) {
+        let light_dir = (light.position - intersect.point).normalize();
+        let brdf = brdf::cook_torrance(&intersect.normal, &view_dir, &light_dir, &base_color, pbr.metallic, pbr.roughness);
+        if brdf == Vec3::zeros() {
+            continue;
+        }
+        let light_radiance = light.color.to_linear() * light.intensity;
+        radiance += brdf.component_mul(&light_radiance) * cast_shadow(intersect, light, ray.time, scene.objects);
+    }
+    radiance
+}
+
+// Intersección más cercana por delante del origen, junto con el índice del objeto
+// intersectado en `objects` y el propio objeto
+fn closest_hit<'a>(ray: &Ray, objects: &'a [Object]) -> Option<(usize, &'a Object, Intersect)> {
+    let mut closest: Option<(usize, &Object, Intersect)> = None;
+
+    for (index, object) in objects.iter().enumerate() {
+        let tmp = object.ray_intersect(ray);
+        if tmp.is_intersecting && tmp.distance > 0.0 && closest.as_ref().is_none_or(|(_, _, hit)| tmp.distance < hit.distance) {
+            closest = Some((index, object, tmp));
+        }
+    }
+
+    closest
+}
+
+// Superficie de un dieléctrico vista desde fuera: brillos de las luces, el rayo reflejado
+// y el refractado que sigue `trace_medium` por el interior, repartidos con Fresnel
+fn shade_dielectric(intersect: &Intersect, medium: &Dielectric, object: &Object, ray: &Ray, scene: &Scene, depth: u32) -> (Vec3, Vec3) {
+    if depth >= MAX_DEPTH {
+        return (intersect.material.diffuse.to_linear(), Vec3::zeros());
+    }
+    let direction = ray.direction.normalize();
+    // Si el rayo viene de dentro (la cámara está sumergida) se sale hacia el aire
+    let entering = direction.dot(&intersect.normal) < 0.0;
+    let (normal, eta) = if entering { (intersect.normal, 1.0 / medium.ior) } else { (-intersect.normal, medium.ior) };
+    let reflectance = brdf::fresnel_dielectric(-direction.dot(&normal), eta);
+
+    let direct = dielectric_highlights(intersect, ray, scene);
+    let mut radiance = direct;
+
+    let reflected = cast_ray(&ray.spawn(intersect.point + normal * 1e-3, reflect(&direction, &normal)), scene, depth + 1);
+    radiance += reflected * reflectance;
+
+    if let Some(refracted) = refract(&direction, &normal, eta) {
+        let refracted = ray.spawn(intersect.point - normal * 1e-3, refracted);
+        let transmitted = if entering {
+            trace_medium(object, medium, &refracted, scene, depth + 1)
+        } else {
+            cast_ray(&refracted, scene, depth + 1)
+        };
+        radiance += transmitted * (1.0 - reflectance);
+    }
+
+    (radiance, direct)
+}
+
+// Brillos de las luces sobre un dieléctrico: solo el lóbulo especular de una superficie
+// muy lisa, el color base negro anula el difuso
+fn dielectric_highlights(intersect: &Intersect, ray: &Ray, scene: &Scene) -> Vec3 {
+    let direction = ray.direction.normalize();
+    let normal = if direction.dot(&intersect.normal) < 0.0 { intersect.normal } else { -intersect.normal };
+    let mut radiance = Vec3::zeros();
+    for light in scene.lights.iter().filter(|light| light.intensity > 0.0) {
+        let light_dir = (light.position - intersect.point).normalize();
+        let highlight = brdf::cook_torrance(&normal, &-direction, &light_dir, &Vec3::zeros(), 0.0, 0.05);
+        radiance += highlight.component_mul(&(light.color.to_linear() * light.intensity)) * cast_shadow(intersect, light, ray.time, scene.objects);
+    }
+    radiance
+}
+
+// Sigue un rayo dentro del medio de `object` hasta que sale o toca algo sumergido,
+// atenuando lo que ve con Beer-Lambert según la distancia recorrida
+fn trace_medium(object: &Object, medium: &Dielectric, ray: &Ray, scene: &Scene, depth: u32) -> Vec3 {
+    let exit = object.ray_spans(ray).into_iter().map(|span| span.exit).find(|exit| exit.distance > 0.0);
+    let exit_distance = exit.as_ref().map_or(f32::INFINITY, |exit| exit.distance);
+
+    // Objetos sumergidos (el fondo del agua) antes de la salida
+    if let Some((_, hit_object, hit)) = closest_hit(ray, scene.objects).filter(|(_, _, hit)| hit.distance < exit_distance) {
+        let (seen, _) = shade(&hit, hit_object, ray, scene, depth);
+        return seen.component_mul(&medium.transmittance(hit.distance));
+    }
+
+    let Some(exit) = exit else {
+        return cast_ray(ray, scene, depth);
+    };
+
+    // Al salir, parte se refracta hacia fuera y parte se refleja de nuevo hacia dentro
+    let direction = &ray.direction;
+    let normal = exit.normal;
+    let reflectance = brdf::fresnel_dielectric(direction.dot(&normal), medium.ior);
+    let mut radiance = Vec3::zeros();
+    if let Some(refracted) = refract(direction, &-normal, medium.ior) {
+        let outside = cast_ray(&ray.spawn(exit.point + normal * 1e-3, refracted), scene, depth + 1);
+        radiance += outside * (1.0 - reflectance);
+    }
+    if depth < MAX_DEPTH {
+        let inside = trace_medium(object, medium, &ray.spawn(exit.point - normal * 1e-3, reflect(direction, &normal)), scene, depth + 1);
+        radiance += inside * reflectance;
+    }
+
+    radiance.component_mul(&medium.transmittance(exit.distance))
+}
+
+// Rayo primario: la radiancia de la superficie vista a través del medio dispersor y de la niebla.
+// Con `aov` rellena además los datos auxiliares de la primera intersección, y la oclusión
+// con `occlusion_pass` si se calcula ese pase.
+fn cast_camera_ray(ray: &Ray, scene: &Scene, aov: Option<&mut AovSample>, occlusion_pass: Option<&AmbientOcclusion>) -> Vec3 {
+    let hit = closest_hit(ray, scene.objects);
+    let distance = hit.as_ref().map_or(f32::INFINITY, |(_, _, intersect)| intersect.distance);
+    let mut color = match hit {
+        Some((index, object, intersect)) => {
+            let (color, direct) = shade(&intersect, object, ray, scene, 0);
+            if let Some(aov) = aov {
+                *aov = AovSample {
+                    depth: intersect.distance,
+                    normal: intersect.normal,
+                    albedo: intersect.material.diffuse.to_linear(),
+                    material_id: intersect.material.id,
+                    object_id: index as u32 + 1,
+                    direct,
+                    indirect: (color - direct).map(|c| c.max(0.0)),
+                    occlusion: occlusion_pass.map_or(1.0, |settings| ambient_occlusion(&intersect, object, settings, ray.time, scene.objects)),
+                };
+            }
+            color
+        }
+        None => background(&ray.direction, scene),
+    };
+
+    let (ray_origin, ray_direction) = (&ray.origin, &ray.direction);
+    if let Some(volume) = &scene.atmosphere.volume {
+        // Desplazamiento pseudoaleatorio por píxel para cambiar bandas por ruido fino
+        let jitter = sampling::hash(sampling::seed(ray_direction));
+        let (transmittance, scattered) = volume.march(ray_origin, ray_direction, distance, scene.lights, jitter, |point, light| {
+            light_visibility(point, light, ray.time, scene.objects)
+        });
+        color = color * transmittance + scattered;
+    }
+    if let Some(fog) = &scene.atmosphere.fog {
+        color = fog.apply(color, ray_origin, ray_direction, distance);
+    }
+    color
+}
+
+fn cast_ray(ray: &Ray, scene: &Scene, depth: u32) -> Vec3 {
+    match closest_hit(ray, scene.objects) {
+        Some((_, object, intersect)) => shade(&intersect, object, ray, scene, depth).0,
+        None => background(&ray.direction, scene),
+    }
+}
+
+// Fondo de los rayos que no tocan nada: el mapa de entorno si lo hay, si no el cielo
+fn background(ray_direction: &Vec3, scene: &Scene) -> Vec3 {
+    match scene.environment {
+        Some(environment) => environment.radiance(ray_direction),
+        None => scene.sky.radiance(ray_direction),
+    }
+}
+
+// Distancia a la que se colocan las luces virtuales del mapa de entorno para las sombras
+const ENVIRONMENT_DISTANCE: f32 = 1000.0;
+
+// Luz del mapa de entorno que refleja una superficie difusa blanca. Los rayos primarios
+// muestrean el mapa según su luminancia y comprueban qué tapa cada dirección; los rebotes
+// y el modo prefiltrado usan la irradiancia precalculada, sin sombras.
+fn environment_diffuse(intersect: &Intersect, environment: &EnvironmentMap, scene: &Scene, time: f32, depth: u32) -> Vec3 {
+    let normal = intersect.normal;
+    if environment.mode == IblMode::Prefiltered || depth > 0 {
+        return environment.irradiance(&normal) / PI;
+    }
+
+    let origin = intersect.point + normal * 1e-3;
+    let shift = sampling::hash2(sampling::seed(&origin));
+    let mut sum = Vec3::zeros();
+    for i in 0..environment.samples {
+        let (u1, u2) = sampling::fibonacci(i, environment.samples, shift);
+        let Some((direction, radiance, pdf)) = environment.sample(u1, u2) else {
+            continue;
+        };
+        let cos_theta = normal.dot(&direction);
+        if cos_theta <= 0.0 {
+            continue;
         }
+        let light = Light::new(origin + direction * ENVIRONMENT_DISTANCE, Color::new(255, 255, 255), 1.0);
+        sum += radiance * (cos_theta / (PI * pdf) * light_visibility(&origin, &light, time, scene.objects));
     }
+    sum / environment.samples as f32
+}
 
-    if !intersect.is_intersecting {
-        return Color::new(4, 12, 36);  // Fondo
+// Radiancia de la superficie intersectada según el modelo de su material, junto con la parte
+// que llega directamente de las fuentes y la emisión, sin rebotes; es lo que separa los pases
+// de iluminación directa e indirecta
+fn shade(intersect: &Intersect, object: &Object, ray: &Ray, scene: &Scene, depth: u32) -> (Vec3, Vec3) {
+    if let Some(pbr) = &intersect.material.pbr {
+        return shade_pbr(intersect, pbr, object, ray, scene, depth);
+    }
+    if let Some(medium) = &intersect.material.dielectric {
+        return shade_dielectric(intersect, medium, object, ray, scene, depth);
     }
+    let diffuse = intersect.material.diffuse.to_linear() * intersect.material.albedo[0];
+    let direct = shade_phong(intersect, ray, scene);
+    (direct + diffuse.component_mul(&ambient_diffuse(intersect, object, scene, ray.time, depth)), direct)
+}
 
-    let mut final_color = Color::new(0, 0, 0);  // Color inicial
+// Luz ambiente y difusa del mapa de entorno que refleja una superficie blanca, atenuadas
+// por la oclusión ambiental. El muestreo del entorno ya tiene sombras y no se atenúa.
+fn ambient_diffuse(intersect: &Intersect, object: &Object, scene: &Scene, time: f32, depth: u32) -> Vec3 {
+    let occlusion = scene.occlusion.map_or(1.0, |settings| ambient_occlusion(intersect, object, settings, time, scene.objects));
+    let mut light = scene.occlusion.map_or(Vec3::zeros(), |settings| settings.ambient * occlusion);
+    if let Some(environment) = scene.environment {
+        let sampled = environment.mode == IblMode::Sampled && depth == 0;
+        light += environment_diffuse(intersect, environment, scene, time, depth) * if sampled { 1.0 } else { occlusion };
+    }
+    light
+}
+
+// Fracción del entorno inmediato de la superficie que no tapa ningún objeto a menos de
+// `settings.distance`, multiplicada por la oclusión por vértice en los mundos de bloques
+fn ambient_occlusion(intersect: &Intersect, object: &Object, settings: &AmbientOcclusion, time: f32, objects: &[Object]) -> f32 {
+    let mut visibility = 1.0;
+    if settings.uses_hemisphere() {
+        let origin = intersect.point + intersect.normal * 1e-3;
+        let directions = settings.directions(&origin, &intersect.normal);
+        let open = directions
+            .iter()
+            .filter(|direction| closest_hit(&Ray::new(origin, **direction, time), objects).is_none_or(|(_, _, hit)| hit.distance > settings.distance))
+            .count();
+        visibility *= open as f32 / directions.len() as f32;
+    }
+    if let (true, Object::World(world)) = (settings.uses_voxels(), object) {
+        visibility *= world.vertex_occlusion(&intersect.point, &intersect.normal);
+    }
+    visibility
+}
+
+// Modelo de Phong original: difuso y especular por cada luz, con Fresnel. Devuelve radiancia
+// lineal como `direct_pbr`, así las luces intensas pasan de 1 y las comprime el mapeo de tonos
+fn shade_phong(intersect: &Intersect, ray: &Ray, scene: &Scene) -> Vec3 {
+    let mut radiance = Vec3::zeros();
+    let base_color = intersect.material.diffuse.to_linear();
 
-    for light in lights {
+    for light in scene.lights.iter().filter(|light| light.intensity > 0.0) {
         let light_dir = (light.position - intersect.point).normalize();
-        let view_dir = (ray_origin - intersect.point).normalize();
+        let view_dir = (ray.origin - intersect.point).normalize();
         let reflect_dir = reflect(&-light_dir, &intersect.normal);
 
         // Diffuse y Specular
-        let diffuse_intensity = intersect.normal.dot(&light_dir).max(0.0).min(1.0);
-        let diffuse = intersect.material.diffuse * intersect.material.albedo[0] * diffuse_intensity * light.intensity;
+        let diffuse_intensity = intersect.normal.dot(&light_dir).clamp(0.0, 1.0);
+        let diffuse = base_color * (intersect.material.albedo[0] * diffuse_intensity * light.intensity);
         let specular_intensity = view_dir.dot(&reflect_dir).max(0.0).powf(intersect.material.specular);
-        let specular = light.color * intersect.material.albedo[1] * specular_intensity * light.intensity;
+        let specular = light.color.to_linear() * (intersect.material.albedo[1] * specular_intensity * light.intensity);
 
         // Fresnel para reflectividad
         let cos_theta = intersect.normal.dot(&view_dir).abs();
         let fresnel_factor = fresnel_schlick(cos_theta, intersect.material.reflectivity);
 
-        // Transparencia y reflectividad ajustados con Fresnel
+        // Reflectividad ajustada con Fresnel
         let reflectivity = intersect.material.reflectivity * fresnel_factor;
-        let transparency = intersect.material.transparency * (1.0 - fresnel_factor);
 
-        final_color = final_color + diffuse * (1.0 - reflectivity) + specular * reflectivity;
+        let shadow = cast_shadow(intersect, light, ray.time, scene.objects);
+        radiance += (diffuse * (1.0 - reflectivity) + specular * reflectivity) * shadow;
+    }
+
+    radiance
+}
 
+// Renderiza la radiancia, le aplica el filtro de ruido (si hay) y los efectos de cámara, y solo
+// al final la pasa a los 8 bits de la pantalla
+pub fn render(framebuffer: &mut Framebuffer, scene: &Scene, camera: &Camera, denoiser: Option<&Denoiser>, post: &PostProcess) {
+    let (width, height) = (framebuffer.width, framebuffer.height);
+    match camera.stereo {
+        None => render_view(framebuffer, scene, camera, (0, 0, width, height)),
+        Some(rig) => render_stereo(framebuffer, scene, camera, rig.layout),
+    }
+    if let Some(denoiser) = denoiser {
+        denoiser.apply(framebuffer);
     }
+    post.apply(framebuffer);
+    framebuffer.tone_map();
+}
 
-    final_color
+// Las dos vistas del par estéreo en el framebuffer según `layout`
+fn render_stereo(framebuffer: &mut Framebuffer, scene: &Scene, camera: &Camera, layout: StereoLayout) {
+    let (width, height) = (framebuffer.width, framebuffer.height);
+
+    let (left, right) = (camera.for_eye(-1.0), camera.for_eye(1.0));
+    match layout {
+        StereoLayout::SideBySide => {
+            render_view(framebuffer, scene, &left, (0, 0, width / 2, height));
+            render_view(framebuffer, scene, &right, (width / 2, 0, width - width / 2, height));
+        }
+        StereoLayout::OverUnder => {
+            render_view(framebuffer, scene, &left, (0, 0, width, height / 2));
+            render_view(framebuffer, scene, &right, (0, height / 2, width, height - height / 2));
+        }
+        StereoLayout::Anaglyph => {
+            // Rojo del ojo izquierdo, verde y azul del derecho
+            render_view(framebuffer, scene, &left, (0, 0, width, height));
+            let left_radiance = framebuffer.radiance.clone();
+            render_view(framebuffer, scene, &right, (0, 0, width, height));
+            for (pixel, left_pixel) in framebuffer.radiance.iter_mut().zip(left_radiance) {
+                pixel.x = left_pixel.x;
+            }
+        }
+    }
 }
 
-pub fn render(framebuffer: &mut Framebuffer, objects: &[Object], camera: &Camera, lights: &[Light]) {
-    let width = framebuffer.width as f32;
-    let height = framebuffer.height as f32;
+// Renderiza la vista de `camera` en el rectángulo `(x, y, ancho, alto)` del framebuffer
+fn render_view(framebuffer: &mut Framebuffer, scene: &Scene, camera: &Camera, viewport: (usize, usize, usize, usize)) {
+    let (left, top, view_width, view_height) = viewport;
+    let width = view_width as f32;
+    let height = view_height as f32;
     let aspect_ratio = width / height;
-    let fov = PI / 2.0;  // Cambia el FOV para ampliar la vista
-    let perspective_scale = (fov * 0.5).tan();
 
-    for y in 0..framebuffer.height {
-        for x in 0..framebuffer.width {
+    // Apertura del cono de cada píxel, que viaja con el rayo para elegir el nivel de mipmap
+    let spread = camera.pixel_spread(height, aspect_ratio);
+
+    // Con la lente abierta o el obturador activo cada píxel promedia varias muestras;
+    // cada una toma un punto de la lente y un instante del obturador
+    let shutter_duration = camera.shutter.map_or(0.0, |shutter| shutter.duration);
+    let lens_samples = if camera.aperture > 0.0 { camera.lens_samples } else { 1 };
+    let motion_samples = if camera.shutter.is_some() { camera.motion_samples } else { 1 };
+    let samples = lens_samples.max(motion_samples).max(1);
+
+    // El pase de oclusión usa la de la escena; si no tiene, los ajustes por defecto
+    let occlusion_pass = framebuffer
+        .aovs
+        .as_ref()
+        .and_then(|aovs| scene.occlusion.copied().or(aovs.occlusion.then(AmbientOcclusion::default)));
+
+    for y in 0..view_height {
+        for x in 0..view_width {
             let screen_x = (2.0 * x as f32) / width - 1.0;
             let screen_y = -(2.0 * y as f32) / height + 1.0;
 
-            let screen_x = screen_x * aspect_ratio * perspective_scale;
-            let screen_y = screen_y * perspective_scale;
+            // Fuera del círculo de los ojos de pez no hay imagen
+            let Some((ray_origin, ray_direction)) = camera.primary_ray(screen_x, screen_y, aspect_ratio) else {
+                framebuffer.set_radiance(left + x, top + y, Vec3::zeros());
+                if let Some(aovs) = framebuffer.aovs.as_mut() {
+                    aovs.set(left + x, top + y, AovSample::default());
+                }
+                continue;
+            };
+
+            let mut aov = framebuffer.aovs.is_some().then(AovSample::default);
+            let radiance = if samples > 1 {
+                // Muestras sobre la lente en una red de Fibonacci, giradas por píxel para
+                // cambiar el patrón repetido del desenfoque por ruido fino. El instante usa
+                // otra secuencia para no quedar ligado al punto de la lente.
+                let (shift_u, shift_v) = sampling::hash2(sampling::seed(&Vec3::new(x as f32, y as f32, 0.0)));
+                let mut sum = Vec3::zeros();
+                let mut sample_aovs = Vec::new();
+                for i in 0..samples {
+                    let (u, v) = sampling::fibonacci(i, samples, (shift_u, shift_v));
+                    let s = if camera.shutter.is_some() { (i as f32 * 0.754878 + shift_u + shift_v).fract() } else { 1.0 };
+
+                    let sample_camera = camera.at_shutter(s);
+                    let (origin, direction) = sample_camera.primary_ray(screen_x, screen_y, aspect_ratio).unwrap_or((ray_origin, ray_direction));
+                    let (origin, direction) = sample_camera.lens_ray(&origin, &direction, u, v);
+                    let ray = Ray::new(origin, direction, scene.time - (1.0 - s) * shutter_duration).with_spread(spread);
+                    let mut sample_aov = aov.map(|_| AovSample::default());
+                    sum += cast_camera_ray(&ray, scene, sample_aov.as_mut(), occlusion_pass.as_ref());
+                    sample_aovs.extend(sample_aov);
+                }
+                if aov.is_some() {
+                    aov = Some(AovSample::average(&sample_aovs));
+                }
+                sum / samples as f32
+            } else {
+                cast_camera_ray(&Ray::new(ray_origin, ray_direction, scene.time).with_spread(spread), scene, aov.as_mut(), occlusion_pass.as_ref())
+            };
+
+            framebuffer.set_radiance(left + x, top + y, radiance);
+            if let (Some(aovs), Some(aov)) = (framebuffer.aovs.as_mut(), aov) {
+                aovs.set(left + x, top + y, aov);
+            }
+        }
+    }
+}
+
+// Autoenfoque: pone el plano de foco a la distancia de lo que se ve en el centro de la pantalla
+fn autofocus(camera: &mut Camera, scene: &Scene) {
+    let forward = camera.forward();
+    if let Some((_, _, hit)) = closest_hit(&Ray::new(camera.eye, forward, scene.time), scene.objects) {
+        camera.focus_distance = hit.distance;
+    }
+}
 
-            let ray_direction = normalize(&Vec3::new(screen_x, screen_y, -1.0));
-            let rotated_direction = camera.base_change(&ray_direction);
+// El sol y la luna del modelo de cielo mueven sus luces y el horizonte tiñe la niebla
+fn update_sky(sky: &mut Sky, lights: &mut [Light], atmosphere: &mut Atmosphere, hours: f32) {
+    sky.set_time(hours);
+    for (light, (position, color, intensity)) in [(0, sky.sun_light(3.0)), (2, sky.moon_light(0.6))] {
+        lights[light].position = position;
+        lights[light].color = color;
+        lights[light].intensity = intensity;
+    }
+    let horizon = Color::from_linear(&sky.radiance(&Vec3::new(-sky.sun_direction.z, 0.02, sky.sun_direction.x)));
+    atmosphere.fog.iter_mut().for_each(|fog| fog.set_color(horizon));
+}
 
-            let pixel_color = cast_ray(&camera.eye, &rotated_direction, objects, lights);
+// Valor que sigue a `flag` en la línea de comandos, si está
+fn arg_value<'a>(args: &'a [String], flag: &str) -> Option<&'a str> {
+    args.iter().position(|arg| arg == flag).and_then(|i| args.get(i + 1)).map(String::as_str)
+}
 
-            framebuffer.set_current_color(pixel_color.to_hex());
-            framebuffer.point(x, y);
+// Valor de una opción con nombres fijos: `default` si no se pasa y error si no es ninguno
+// de `choices`, para que una errata no cambie el render sin avisar
+fn arg_choice<T: Copy>(args: &[String], flag: &str, choices: &[(&str, T)], default: T) -> T {
+    let Some(value) = arg_value(args, flag) else {
+        return default;
+    };
+    match choices.iter().find(|(name, _)| *name == value) {
+        Some(&(_, choice)) => choice,
+        None => {
+            let names: Vec<&str> = choices.iter().map(|(name, _)| *name).collect();
+            panic!("Valor desconocido para {}: {} (opciones: {})", flag, value, names.join("|"))
         }
     }
 }
@@ -115,190 +637,645 @@ pub fn render(framebuffer: &mut Framebuffer, objects: &[Object], camera: &Camera
 fn main() {
     let window_width = 800;
     let window_height = 600;
-    let framebuffer_width = 200;  // Reducir la resolución
-    let framebuffer_height = 150;  // Reducir la resolución
 
     let frame_delay = Duration::from_millis(16);
 
-    let mut framebuffer = Framebuffer::new(framebuffer_width, framebuffer_height);
+    let args: Vec<String> = std::env::args().collect();
 
-    let mut window = Window::new(
-        "Mini Minecraft",
-        window_width,
-        window_height,
-        WindowOptions::default(),
-    ).unwrap();
-
-    let rubber = Material::new(
-        Color::new(80, 0, 0),
-        1.0,
-        [0.9, 0.1],
-        0.9,
-        0.5,
-    );
-
-    // Carga las texturas
-    let grass_texture = Texture::load_from_file("src/grass.png");
-    let stone_texture = Texture::load_from_file("src/stone.png");
-    let wood_texture = Texture::load_from_file("src/wood.png"); // Carga la textura de la madera
-    let skybox_texture = Texture::load_from_file("src/skybox.png");  // Carga la textura del cielo
-
-    // Skybox
-    let skybox = Object::Cube(Cube {
-        min: Vec3::new(-50.0, -50.0, -50.0),
-        max: Vec3::new(50.0, 50.0, 50.0),
-        material: Material::with_texture(Color::new(135, 206, 235), 1.0, [0.0, 0.0], &skybox_texture, 0.0, 0.0),
-        is_skybox: true,  // Marcamos este cubo como el skybox
+    // Resolución de render con `--resolution <ancho>x<alto>`; por defecto reducida para ir en tiempo real
+    let (framebuffer_width, framebuffer_height) = arg_value(&args, "--resolution").map_or((200, 150), |resolution| {
+        let (width, height) = resolution.split_once('x').expect("La resolución debe tener la forma <ancho>x<alto>");
+        (width.parse().expect("El ancho debe ser un número entero"), height.parse().expect("El alto debe ser un número entero"))
     });
+    let mut framebuffer = Framebuffer::new(framebuffer_width, framebuffer_height);
+    // `--aovs` calcula los pases auxiliares; en `--batch` se guardan junto a cada fotograma
+    if args.iter().any(|arg| arg == "--aovs") {
+        framebuffer.enable_aovs().occlusion = true;
+    }
+    // `--denoise` filtra el ruido de la lente, el obturador y el volumen con los pases auxiliares
+    let denoiser = Denoiser::default();
+    let mut denoise = args.iter().any(|arg| arg == "--denoise");
+    if denoise {
+        framebuffer.enable_aovs();
+    }
 
-    // Crea los árboles
-    let mut objects: Vec<Object> = Vec::new();
-    objects.extend(create_tree(-1.5, -4.0, 3.0, 1.0, &wood_texture, &grass_texture));  // Árbol 1
-    objects.extend(create_tree(1.5, -5.0, 4.0, 1.5, &wood_texture, &grass_texture));  // Árbol 2
-    objects.extend(create_tree(0.0, -6.0, 2.0, 1.0, &wood_texture, &grass_texture));  // Árbol 3
-    objects.extend(create_tree(3.0, -6.0, 3.0, 1.2, &wood_texture, &grass_texture)); // Árbol 4 (más a la derecha)
-    objects.extend(create_tree(-3.0, -3.0, 3.5, 1.0, &wood_texture, &grass_texture)); // Árbol 5 (más hacia adelante y a la izquierda)
-    objects.extend(create_tree(2.0, -8.0, 2.8, 1.2, &wood_texture, &grass_texture)); // Árbol 6 (un poco más al fondo)
-
-    // Otros objetos en la escena
-    objects.push(Object::Cube(Cube {
-        min: Vec3::new(-2.0, -1.0, -4.0),
-        max: Vec3::new(-1.0, 1.0, -3.0),
-        material: Material::with_texture(Color::new(255, 255, 255), 1.0, [0.7, 0.3], &stone_texture, 0.3, 0.0),
-        is_skybox: false,
-    }));
+    // Efectos de cámara sobre la radiancia antes de recortarla: `--bloom` (umbral `--bloom-threshold`,
+    // fuerza `--bloom-intensity`), `--flare` (con `--flare-ghosts` fantasmas) y `--vignette`
+    // (fuerza `--vignette-strength`)
+    let mut post = PostProcess::default();
+    post.bloom.enabled = args.iter().any(|arg| arg == "--bloom");
+    if let Some(threshold) = arg_value(&args, "--bloom-threshold") {
+        post.bloom.threshold = threshold.parse().expect("El umbral del resplandor debe ser un número");
+    }
+    if let Some(intensity) = arg_value(&args, "--bloom-intensity") {
+        post.bloom.intensity = intensity.parse().expect("La intensidad del resplandor debe ser un número");
+    }
+    post.flare.enabled = args.iter().any(|arg| arg == "--flare");
+    if let Some(ghosts) = arg_value(&args, "--flare-ghosts") {
+        post.flare.ghosts = ghosts.parse().expect("El número de fantasmas debe ser un número entero");
+    }
+    post.vignette.enabled = args.iter().any(|arg| arg == "--vignette");
+    if let Some(strength) = arg_value(&args, "--vignette-strength") {
+        post.vignette.strength = strength.parse().expect("La fuerza del viñeteado debe ser un número");
+    }
 
-        // Piedra 2 (más adelante)
-    objects.push(Object::Cube(Cube {
-        min: Vec3::new(-1.5, -1.0, -3.5),
-        max: Vec3::new(-0.5, 0.5, -2.5),
-        material: Material::with_texture(Color::new(200, 200, 200), 1.0, [0.7, 0.3], &stone_texture, 0.3, 0.0),  // Pasar referencia
-        is_skybox: false,
-    }));
+    let heightmap_path = arg_value(&args, "--heightmap");
+    let world_seed = arg_value(&args, "--world").map(|seed| {
+        seed.parse::<u64>().expect("La semilla del mundo debe ser un número entero")
+    });
 
-    // Piedra 3 (más pequeña, a la derecha)
-    objects.push(Object::Cube(Cube {
-        min: Vec3::new(2.5, -0.5, -4.5),
-        max: Vec3::new(3.0, 0.0, -4.0),
-        material: Material::with_texture(Color::new(180, 180, 180), 1.0, [0.7, 0.3], &stone_texture, 0.3, 0.0),  // Pasar referencia
-        is_skybox: false,
-    }));
+    // Filtrado y repetición de las texturas: `--filter nearest|bilinear|trilinear`, `--wrap repeat|clamp|mirror`
+    let filter = arg_choice(&args, "--filter", &[("nearest", Filter::Nearest), ("bilinear", Filter::Bilinear), ("trilinear", Filter::Trilinear)], Filter::Nearest);
+    let wrap = arg_choice(&args, "--wrap", &[("repeat", WrapMode::Repeat), ("clamp", WrapMode::Clamp), ("mirror", WrapMode::Mirror)], WrapMode::Repeat);
 
+    // Carga las texturas
+    let grass_texture = Texture::load_from_file("src/grass.png").with_sampling(filter, wrap);
+    let stone_texture = Texture::load_from_file("src/stone.png").with_sampling(filter, wrap);
+    let wood_texture = Texture::load_from_file("src/wood.png").with_sampling(filter, wrap); // Carga la textura de la madera
 
+    // Registro de bloques compartido: un solo atlas para todas las caras
+    let mut registry = world::block_registry(&grass_texture, &stone_texture, &wood_texture);
+    registry.atlas.texture = registry.atlas.texture.with_sampling(filter, wrap);
+    let registry = Rc::new(registry);
 
-    objects.push(Object::Sphere(Sphere {
-        center: Vec3::new(1.5, 4.0, -6.0), // Elevamos la esfera para simular el sol
-        radius: 0.5,
-        material: Material::new(Color::new(255, 255, 0), 1.0, [1.0, 1.0], 0.0, 0.0),  // Esfera brillante
-    }));
+    // Con `--world <semilla>` se genera un mundo procedural; si no, la escena hecha a mano
+    let objects: Vec<Object> = match world_seed {
+        Some(seed) => {
+            println!("Generando mundo con semilla {}", seed);
+            vec![Object::World(create_world(seed, &registry))]
+        }
+        None => create_scene(heightmap_path, &grass_texture, &stone_texture, &registry),
+    };
 
     let mut camera = Camera::new(
-        Vec3::new(0.0, 0.0, 5.0),  // Posición de la cámara
+        if world_seed.is_some() { Vec3::new(0.0, 6.0, 16.0) } else { Vec3::new(0.0, 0.0, 5.0) },  // Posición de la cámara
         Vec3::new(0.0, 0.0, 0.0),  // Hacia dónde mira la cámara
         Vec3::new(0.0, 1.0, 0.0),  // Arriba
     );
 
+    // Profundidad de campo: `--aperture <radio>`, `--focus <distancia>` o `--autofocus`,
+    // y `--bokeh <láminas>` para un diafragma poligonal (por defecto, circular)
+    let aperture: f32 = arg_value(&args, "--aperture").map_or(0.0, |a| a.parse().expect("La apertura debe ser un número"));
+    let focus_distance = arg_value(&args, "--focus").map_or(camera.focus_distance, |f| f.parse().expect("La distancia de foco debe ser un número"));
+    let bokeh = match arg_value(&args, "--bokeh").map(|b| b.parse::<u32>().expect("Las láminas del diafragma deben ser un número entero")) {
+        Some(blades) if blades >= 3 => Bokeh::Polygon { blades, rotation: PI / 2.0 },
+        _ => Bokeh::Circle,
+    };
+    camera = camera.with_lens(aperture, focus_distance, bokeh);
+
+    // Proyección con `--projection perspective|ortho|fisheye|equisolid|equirect`, campo de visión
+    // con `--fov <grados>` medido sobre `--fov-axis vertical|horizontal`
+    let fov = arg_value(&args, "--fov").map_or(90.0, |f| f.parse::<f32>().expect("El FOV debe ser un número en grados")).to_radians();
+    let fov_axis = arg_choice(&args, "--fov-axis", &[("vertical", FovAxis::Vertical), ("horizontal", FovAxis::Horizontal)], FovAxis::Vertical);
+    let projection = arg_choice(
+        &args,
+        "--projection",
+        &[
+            ("perspective", Projection::Perspective),
+            ("ortho", Projection::Orthographic { half_height: 6.0 }),
+            ("fisheye", Projection::FisheyeEquidistant),
+            ("equisolid", Projection::FisheyeEquisolid),
+            ("equirect", Projection::Equirectangular),
+        ],
+        Projection::Perspective,
+    );
+    camera = camera.with_projection(projection, fov, fov_axis);
+    camera.autofocus = args.iter().any(|arg| arg == "--autofocus");
+
+    // Desenfoque de movimiento con `--shutter <fracción>`: parte del intervalo entre cuadros
+    // que el obturador queda abierto (0.5 equivale a un obturador de 180°)
+    let shutter: f32 = arg_value(&args, "--shutter").map_or(0.0, |s| s.parse().expect("El obturador debe ser un número entre 0 y 1"));
+    let shutter = shutter.clamp(0.0, 1.0);
+
+    // Estéreo con `--stereo sbs|ou|anaglyph`, distancia interpupilar `--ipd` y distancia de
+    // convergencia `--convergence` (por defecto, la del objetivo de la cámara)
+    let layout = arg_choice(
+        &args,
+        "--stereo",
+        &[("sbs", Some(StereoLayout::SideBySide)), ("ou", Some(StereoLayout::OverUnder)), ("anaglyph", Some(StereoLayout::Anaglyph))],
+        None,
+    );
+    camera.stereo = layout.map(|layout| StereoRig {
+        ipd: arg_value(&args, "--ipd").map_or(0.1, |ipd| ipd.parse().expect("La distancia interpupilar debe ser un número")),
+        convergence: arg_value(&args, "--convergence").map_or((camera.center - camera.eye).magnitude(), |c| c.parse().expect("La convergencia debe ser un número")),
+        layout,
+    });
+
     let mut lights = vec![
-        Light::new(Vec3::new(100.0, 100.0, 10.0), Color::new(255, 255, 255), 3.0), // Luz principal
+        Light::new(Vec3::new(100.0, 100.0, 10.0), Color::new(255, 255, 255), 3.0), // Sol, lo mueve el cielo
         Light::new(Vec3::new(-50.0, 50.0, 20.0), Color::new(255, 100, 100), 2.0),  // Luz roja adicional
+        Light::new(Vec3::new(-100.0, -100.0, -10.0), Color::new(0, 0, 0), 0.0),  // Luna, la mueve el cielo
     ];
 
-    let sun = Object::Sphere(Sphere {
-        center: lights[0].position,  // El Sol en la misma posición que la luz principal
-        radius: 5.0,  // Ajusta el tamaño del sol
-        material: Material::new(Color::new(255, 255, 0), 100.0, [1.0, 0.0], 0.0, 0.0),  // Color amarillo brillante
+    // Cielo físico: la hora (`--time`, en horas) y la latitud (`--latitude`, en grados) fijan el sol
+    // El reloj avanza `--clock-speed` horas por segundo real; `--paused` lo arranca detenido
+    let time_of_day: f32 = arg_value(&args, "--time").map_or(10.0, |t| t.parse().expect("La hora debe ser un número"));
+    let clock_speed: f32 = arg_value(&args, "--clock-speed").map_or(0.1, |s| s.parse().expect("La velocidad del reloj debe ser un número"));
+    let mut clock = Clock::new(time_of_day, clock_speed);
+    clock.paused = args.iter().any(|arg| arg == "--paused");
+    let latitude: f32 = arg_value(&args, "--latitude").map_or(40.0, |l| l.parse().expect("La latitud debe ser un número"));
+    let mut sky = Sky::new(3.0, latitude, 172);
+
+    // Mapa de entorno equirrectangular con `--env <archivo.hdr|.exr>`: sustituye al cielo como fondo
+    // e ilumina la escena, con `--env-intensity` y `--env-rotation <grados>`. `--ibl sampled` muestrea
+    // el mapa con `--ibl-samples` rayos por punto, con sombras; por defecto se usa la irradiancia prefiltrada.
+    let environment = arg_value(&args, "--env").map(|path| {
+        let mode = arg_choice(&args, "--ibl", &[("prefiltered", IblMode::Prefiltered), ("sampled", IblMode::Sampled)], IblMode::Prefiltered);
+        let samples = arg_value(&args, "--ibl-samples").map_or(16, |n| n.parse().expect("Las muestras del entorno deben ser un número entero"));
+        EnvironmentMap::load(path)
+            .with_intensity(arg_value(&args, "--env-intensity").map_or(1.0, |i| i.parse().expect("La intensidad del entorno debe ser un número")))
+            .with_rotation(arg_value(&args, "--env-rotation").map_or(0.0, |r: &str| r.parse::<f32>().expect("El giro del entorno debe ser un número").to_radians()))
+            .with_mode(mode, samples)
     });
-    
 
-    // Agregar el sol a la lista de objetos
-    objects.push(sun);
+    // Oclusión ambiental con `--ao hemisphere|voxel|both`: una luz ambiente de nivel `--ambient`
+    // que se oscurece en los rincones, con `--ao-samples` rayos de alcance `--ao-distance`
+    // (`hemisphere`) o según los bloques vecinos en el mundo de bloques (`voxel`)
+    let occlusion = arg_value(&args, "--ao").map(|_| {
+        let defaults = AmbientOcclusion::default();
+        let level = arg_value(&args, "--ambient").map(|l| l.parse::<f32>().expect("El nivel de luz ambiente debe ser un número"));
+        AmbientOcclusion {
+            mode: arg_choice(
+                &args,
+                "--ao",
+                &[("hemisphere", OcclusionMode::Hemisphere), ("voxel", OcclusionMode::Voxel), ("both", OcclusionMode::Both)],
+                defaults.mode,
+            ),
+            samples: arg_value(&args, "--ao-samples").map_or(defaults.samples, |n| n.parse().expect("Las muestras de oclusión deben ser un número entero")),
+            distance: arg_value(&args, "--ao-distance").map_or(defaults.distance, |d| d.parse().expect("El alcance de la oclusión debe ser un número")),
+            ambient: level.map_or(defaults.ambient, |level| Vec3::new(level, level, level)),
+        }
+    });
+
+    // Niebla con `--fog exp|height|none` (por defecto, según la altura) y haces de luz con `--volumetric`
+    let height_fog = Some(Fog::Height { color: Color::new(150, 170, 200), density: 0.06, falloff: 0.8, base_height: -1.0 });
+    let exponential_fog = Some(Fog::Exponential { color: Color::new(150, 170, 200), density: 0.04 });
+    let mut atmosphere = Atmosphere {
+        fog: arg_choice(&args, "--fog", &[("height", height_fog), ("exp", exponential_fog), ("none", None)], height_fog),
+        volume: None,
+    };
+    if args.iter().any(|arg| arg == "--volumetric") {
+        let (min, max) = if world_seed.is_some() {
+            (Vec3::new(-12.0, -6.0, -12.0), Vec3::new(12.0, 6.0, 12.0))
+        } else {
+            (Vec3::new(-6.0, -1.5, -10.0), Vec3::new(6.0, 4.0, 2.0))
+        };
+        atmosphere.volume = Some(ScatteringVolume::new(min, max, 0.12, 0.01, 0.3));
+    }
+
+    // Animación de cámara: `--path <archivo>` con fotogramas clave o `--turntable <segundos>` para
+    // dar una vuelta alrededor del objetivo; `--interpolation catmull-rom|bezier` y
+    // `--easing linear|in|out|in-out` controlan cómo se recorre
+    let interpolation = arg_choice(&args, "--interpolation", &[("catmull-rom", Interpolation::CatmullRom), ("bezier", Interpolation::Bezier)], Interpolation::CatmullRom);
+    let easing = arg_choice(
+        &args,
+        "--easing",
+        &[("linear", Easing::Linear), ("in", Easing::EaseIn), ("out", Easing::EaseOut), ("in-out", Easing::EaseInOut)],
+        Easing::Linear,
+    );
+    let camera_path = match (arg_value(&args, "--path"), arg_value(&args, "--turntable")) {
+        (Some(path), _) => Some(CameraPath::load(path, interpolation, easing)),
+        (None, Some(seconds)) => {
+            let duration = seconds.parse().expect("La duración de la vuelta debe ser un número");
+            let mut turntable = CameraPath::turntable(camera.center, camera.eye, camera.fov, duration);
+            turntable.easing = easing;
+            Some(turntable)
+        }
+        (None, None) => None,
+    };
+
+    // Con `--batch <carpeta>` se renderiza el recorrido sin ventana, una imagen numerada por
+    // fotograma a `--fps` fotogramas por segundo, y se termina. `--format png|hdr|exr` elige el
+    // formato: los dos últimos guardan la radiancia lineal sin recortar, y en EXR los pases
+    // auxiliares van como capas del mismo archivo con `--exr-precision half|float`.
+    if let Some(directory) = arg_value(&args, "--batch") {
+        let path = camera_path.expect("--batch necesita un recorrido: --path <archivo> o --turntable <segundos>");
+        let fps: f32 = arg_value(&args, "--fps").map_or(24.0, |f| f.parse().expect("Los fotogramas por segundo deben ser un número"));
+        let format = arg_choice(&args, "--format", &[("png", "png"), ("hdr", "hdr"), ("exr", "exr")], "png");
+        let precision = arg_choice(&args, "--exr-precision", &[("half", Precision::Half), ("float", Precision::Float)], Precision::Half);
+        std::fs::create_dir_all(directory).expect("Error al crear la carpeta de salida");
 
+        // En un recorrido cerrado el último fotograma repetiría el primero
+        let frames = ((path.duration() * fps).round() as usize + usize::from(!path.closed)).max(1);
+        for frame in 0..frames {
+            let time = frame as f32 / fps;
+            path.apply(&mut camera, path.keyframes[0].time + time);
+            if shutter > 0.0 {
+                let open = path.sample(path.keyframes[0].time + time - shutter / fps);
+                camera.shutter = Some(Shutter { open_eye: open.eye, open_center: open.center, duration: shutter / fps });
+            }
+            update_sky(&mut sky, &mut lights, &mut atmosphere, clock.hours);
+            let scene = Scene { objects: &objects, lights: &lights, sky: &sky, environment: environment.as_ref(), occlusion: occlusion.as_ref(), atmosphere: &atmosphere, time };
+            if camera.autofocus {
+                autofocus(&mut camera, &scene);
+            }
+            render(&mut framebuffer, &scene, &camera, denoise.then_some(&denoiser), &post);
 
-    let mut time_of_day = 0;  // 0 = Día, 1 = Tarde, 2 = Noche
-    let rotation_speed = PI / 10.0;
+            let file = format!("{}/frame_{:04}.{}", directory, frame + 1, format);
+            match format {
+                "exr" => framebuffer.save_exr(&file, precision).expect("Error al guardar el fotograma"),
+                "hdr" => framebuffer.save_hdr(&file).expect("Error al guardar el fotograma"),
+                _ => framebuffer.save(&file).expect("Error al guardar el fotograma"),
+            }
+            if let (Some(aovs), false) = (&framebuffer.aovs, format == "exr") {
+                aovs.save(&format!("{}/frame_{:04}", directory, frame + 1)).expect("Error al guardar los pases auxiliares");
+            }
+            println!("Fotograma {}/{}: {}", frame + 1, frames, file);
+            clock.advance(1.0 / fps, 1.0);
+        }
+        return;
+    }
+
+    let mut window = Window::new(
+        "Mini Minecraft",
+        window_width,
+        window_height,
+        WindowOptions::default(),
+    ).unwrap();
 
-    let zoom_speed = 0.5;  // Velocidad de zoom
+    // En la ventana el recorrido se reproduce en bucle; Intro lo pausa para mover la cámara a mano
+    let mut playing = camera_path.is_some();
+    let mut playback_time = 0.0;
+    let mut scene_time = 0.0;
+    let mut display_pass = Pass::Beauty;
+
+    // Velocidades por segundo, multiplicadas por la duración de cada cuadro
+    let rotation_speed = PI / 2.0;  // Radianes por segundo al orbitar
+    let zoom_speed = 3.0;           // Unidades por segundo
+    let fly_speed = 4.0;            // Unidades por segundo en primera persona
+    let mouse_sensitivity = 0.005;  // Radianes por píxel de movimiento del ratón
+    let mut fly_mode = false;
+    let mut last_mouse: Option<(f32, f32)> = None;
+    let mut last_frame = Instant::now();
 
 while window.is_open() && !window.is_key_down(Key::Escape) {
-    if window.is_key_down(Key::Left) {
-        camera.orbit(rotation_speed, 0.0);
+    let now = Instant::now();
+    let dt = (now - last_frame).as_secs_f32();
+    last_frame = now;
+    scene_time += dt;
+    let (previous_eye, previous_center) = (camera.eye, camera.center);
+
+    // Tab alterna entre orbitar alrededor del objetivo y volar en primera persona
+    if window.is_key_pressed(Key::Tab, KeyRepeat::No) {
+        fly_mode = !fly_mode;
+    }
+
+    if fly_mode {
+        // WASD para moverse, Espacio y Mayúsculas para subir y bajar
+        let axis = |positive: Key, negative: Key| {
+            (window.is_key_down(positive) as i32 - window.is_key_down(negative) as i32) as f32
+        };
+        let step = fly_speed * dt;
+        camera.fly(axis(Key::W, Key::S) * step, axis(Key::D, Key::A) * step, axis(Key::Space, Key::LeftShift) * step);
+
+        // Mirar con el ratón mientras se mantiene el botón izquierdo
+        let mouse = window.get_mouse_pos(MouseMode::Pass);
+        if window.get_mouse_down(MouseButton::Left) {
+            if let (Some((x, y)), Some((last_x, last_y))) = (mouse, last_mouse) {
+                camera.look((x - last_x) * mouse_sensitivity, -(y - last_y) * mouse_sensitivity);
+            }
+        }
+        last_mouse = mouse;
+    } else {
+        let angle = rotation_speed * dt;
+        if window.is_key_down(Key::Left) {
+            camera.orbit(angle, 0.0);
+        }
+
+        if window.is_key_down(Key::Right) {
+            camera.orbit(-angle, 0.0);
+        }
+
+        if window.is_key_down(Key::Up) {
+            camera.orbit(0.0, -angle);
+        }
+
+        if window.is_key_down(Key::Down) {
+            camera.orbit(0.0, angle);
+        }
+
+        // Control de zoom
+        if window.is_key_down(Key::Z) {  // Zoom in (acercar)
+            camera.zoom_in(zoom_speed * dt);
+        }
+        if window.is_key_down(Key::X) {  // Zoom out (alejar)
+            camera.zoom_out(zoom_speed * dt);
+        }
+    }
+
+    // Ciclo de día y noche: P pausa el reloj, mantener T lo acelera y +/- cambian su velocidad
+    if window.is_key_pressed(Key::P, KeyRepeat::No) {
+        clock.toggle_pause();
+    }
+    if window.is_key_pressed(Key::Equal, KeyRepeat::No) {
+        clock.scale_speed(2.0);
+    }
+    if window.is_key_pressed(Key::Minus, KeyRepeat::No) {
+        clock.scale_speed(0.5);
+    }
+    let fast_forward = if window.is_key_down(Key::T) { 20.0 } else { 1.0 };
+    clock.advance(dt, fast_forward);
+
+    update_sky(&mut sky, &mut lights, &mut atmosphere, clock.hours);
+
+    // Campo de visión en vivo: AvPág lo abre y RePág lo cierra, al doble de tamaño por segundo
+    if window.is_key_down(Key::PageDown) {
+        camera.adjust_fov(2f32.powf(dt));
+    }
+    if window.is_key_down(Key::PageUp) {
+        camera.adjust_fov(0.5f32.powf(dt));
+    }
+
+    // Apertura en vivo: [ la cierra y ] la abre
+    if window.is_key_down(Key::LeftBracket) {
+        camera.aperture = (camera.aperture - 0.5 * dt).max(0.0);
+    }
+    if window.is_key_down(Key::RightBracket) {
+        camera.aperture += 0.5 * dt;
     }
 
-    if window.is_key_down(Key::Right) {
-        camera.orbit(-rotation_speed, 0.0);
+    if let Some(path) = &camera_path {
+        if window.is_key_pressed(Key::Enter, KeyRepeat::No) {
+            playing = !playing;
+        }
+        if playing {
+            playback_time = (playback_time + dt) % path.duration().max(1e-3);
+            path.apply(&mut camera, path.keyframes[0].time + playback_time);
+        }
     }
 
-    if window.is_key_down(Key::Up) {
-        camera.orbit(0.0, -rotation_speed);
+    // El obturador abarca el final del intervalo desde el cuadro anterior
+    if shutter > 0.0 {
+        camera.shutter = Some(Shutter {
+            open_eye: previous_eye.lerp(&camera.eye, 1.0 - shutter),
+            open_center: previous_center.lerp(&camera.center, 1.0 - shutter),
+            duration: shutter * dt,
+        });
     }
 
-    if window.is_key_down(Key::Down) {
-        camera.orbit(0.0, rotation_speed);
+    let scene = Scene { objects: &objects, lights: &lights, sky: &sky, environment: environment.as_ref(), occlusion: occlusion.as_ref(), atmosphere: &atmosphere, time: scene_time };
+    if camera.autofocus {
+        autofocus(&mut camera, &scene);
     }
 
-    // Control de zoom
-    if window.is_key_down(Key::Z) {  // Zoom in (acercar)
-        camera.zoom_in(zoom_speed);
+    // B, F y G activan o desactivan el resplandor, los destellos de la lente y el viñeteado
+    if window.is_key_pressed(Key::B, KeyRepeat::No) {
+        post.bloom.enabled = !post.bloom.enabled;
+    }
+    if window.is_key_pressed(Key::F, KeyRepeat::No) {
+        post.flare.enabled = !post.flare.enabled;
     }
-    if window.is_key_down(Key::X) {  // Zoom out (alejar)
-        camera.zoom_out(zoom_speed);
+    if window.is_key_pressed(Key::G, KeyRepeat::No) {
+        post.vignette.enabled = !post.vignette.enabled;
     }
 
-    // Ciclo de día y noche controlado por teclas
-    if window.is_key_down(Key::D) {
-        time_of_day = (time_of_day + 1) % 3;  // Cambia entre Día, Tarde y Noche
+    // N activa o desactiva el filtro de ruido. Los pases auxiliares que lo guían se activan
+    // antes de renderizar, así el primer cuadro filtrado ya los tiene
+    if window.is_key_pressed(Key::N, KeyRepeat::No) {
+        denoise = !denoise;
+        framebuffer.enable_aovs();
     }
 
-    // Cambios en la iluminación según la hora del día
-    match time_of_day {
-        0 => {
-            lights[0].color = Color::new(255, 255, 255);  // Luz blanca para el día
-            lights[0].intensity = 3.0;
-        },
-        1 => {
-            lights[0].color = Color::new(255, 165, 0);  // Luz anaranjada para la tarde
-            lights[0].intensity = 2.0;
-        },
-        2 => {
-            lights[0].color = Color::new(0, 0, 139);  // Luz azul para la noche
-            lights[0].intensity = 1.0;
-        },
-        _ => {}
+    // V recorre los pases auxiliares; se empiezan a calcular la primera vez que se piden
+    if window.is_key_pressed(Key::V, KeyRepeat::No) {
+        display_pass = display_pass.next();
+        framebuffer.enable_aovs().occlusion |= display_pass == Pass::Occlusion;
+        window.set_title(&format!("Mini Minecraft - {}", display_pass.name()));
     }
+    render(&mut framebuffer, &scene, &camera, denoise.then_some(&denoiser), &post);
 
-    render(&mut framebuffer, &objects, &camera, &lights);
+    let visualized = framebuffer.aovs.as_ref().filter(|_| display_pass != Pass::Beauty).map(|aovs| aovs.visualize(display_pass));
 
     window
-        .update_with_buffer(&framebuffer.buffer, framebuffer_width, framebuffer_height)
+        .update_with_buffer(visualized.as_ref().unwrap_or(&framebuffer.buffer), framebuffer_width, framebuffer_height)
         .unwrap();
 
     std::thread::sleep(frame_delay);
 }
 }
 
-fn create_tree(base_x: f32, base_z: f32, trunk_height: f32, leaves_size: f32, wood_texture: &Texture, grass_texture: &Texture) -> Vec<Object> {
+fn create_tree(base_x: f32, base_z: f32, trunk_height: f32, leaves_size: f32, registry: &Rc<BlockRegistry>) -> Vec<Object> {
     let mut tree_objects = Vec::new();
     
     // Tronco (cubos verticales)
     for i in 0..(trunk_height as i32) {
-        tree_objects.push(Object::Cube(Cube {
-            min: Vec3::new(base_x - 0.25, i as f32 - 1.0, base_z - 0.25),
-            max: Vec3::new(base_x + 0.25, (i as f32) + 0.25, base_z + 0.25),
-            material: Material::with_texture(Color::new(139, 69, 19), 1.0, [0.7, 0.3], &wood_texture.clone(), 0.1, 0.0),  // Clonamos la textura
-            is_skybox: false,
-        }));
+        tree_objects.push(Object::Block(BlockCube::new(
+            Vec3::new(base_x - 0.25, i as f32 - 1.0, base_z - 0.25),
+            Vec3::new(base_x + 0.25, (i as f32) + 0.25, base_z + 0.25),
+            Block::Wood.id(),
+            registry,
+        )));
     }
 
     // Hojas (cubos grandes encima del tronco)
     let leaves_base_y = trunk_height - 0.5;  // Altura donde empiezan las hojas
-    tree_objects.push(Object::Cube(Cube {
-        min: Vec3::new(base_x - leaves_size, leaves_base_y, base_z - leaves_size),
-        max: Vec3::new(base_x + leaves_size, leaves_base_y + leaves_size, base_z + leaves_size),
-        material: Material::with_texture(Color::new(34, 139, 34), 1.0, [0.7, 0.3], &grass_texture.clone(), 0.1, 0.0), // Clonamos la textura
+    tree_objects.push(Object::Block(BlockCube::new(
+        Vec3::new(base_x - leaves_size, leaves_base_y, base_z - leaves_size),
+        Vec3::new(base_x + leaves_size, leaves_base_y + leaves_size, base_z + leaves_size),
+        Block::Leaves.id(),
+        registry,
+    )));
+
+    tree_objects
+}
+
+// Escena hecha a mano: terreno, árboles, piedras y decoraciones
+fn create_scene(heightmap_path: Option<&str>, grass_texture: &Texture, stone_texture: &Texture, registry: &Rc<BlockRegistry>) -> Vec<Object> {
+    // Terreno: desde un mapa de alturas si se pasa `--heightmap <ruta>`, si no colinas procedurales
+    let mut objects: Vec<Object> = Vec::new();
+    objects.push(Object::Terrain(Box::new(create_terrain(heightmap_path, grass_texture, stone_texture))));
+
+    // Crea los árboles
+    objects.extend(create_tree(-1.5, -4.0, 3.0, 1.0, registry));  // Árbol 1
+    objects.extend(create_tree(1.5, -5.0, 4.0, 1.5, registry));  // Árbol 2
+    objects.extend(create_tree(0.0, -6.0, 2.0, 1.0, registry));  // Árbol 3
+    objects.extend(create_tree(3.0, -6.0, 3.0, 1.2, registry)); // Árbol 4 (más a la derecha)
+    objects.extend(create_tree(-3.0, -3.0, 3.5, 1.0, registry)); // Árbol 5 (más hacia adelante y a la izquierda)
+    objects.extend(create_tree(2.0, -8.0, 2.8, 1.2, registry)); // Árbol 6 (un poco más al fondo)
+
+    // Otros objetos en la escena
+    objects.push(Object::Cube(Cube {
+        min: Vec3::new(-2.0, -1.0, -4.0),
+        max: Vec3::new(-1.0, 1.0, -3.0),
+        material: Material::with_texture(Color::new(255, 255, 255), 1.0, [0.7, 0.3], stone_texture, 0.3, 0.0),
         is_skybox: false,
     }));
 
-    tree_objects
+        // Piedra 2 (más adelante)
+    objects.push(Object::Cube(Cube {
+        min: Vec3::new(-1.5, -1.0, -3.5),
+        max: Vec3::new(-0.5, 0.5, -2.5),
+        material: Material::with_texture(Color::new(200, 200, 200), 1.0, [0.7, 0.3], stone_texture, 0.3, 0.0),  // Pasar referencia
+        is_skybox: false,
+    }));
+
+    // Piedra 3 (más pequeña, a la derecha)
+    objects.push(Object::Cube(Cube {
+        min: Vec3::new(2.5, -0.5, -4.5),
+        max: Vec3::new(3.0, 0.0, -4.0),
+        material: Material::with_texture(Color::new(180, 180, 180), 1.0, [0.7, 0.3], stone_texture, 0.3, 0.0),  // Pasar referencia
+        is_skybox: false,
+    }));
+
+
+
+    // Estanque: el agua se ve más azul donde el fondo del terreno queda más hondo
+    objects.push(Object::Cube(Cube::new(
+        Vec3::new(-1.2, -1.6, -1.6),
+        Vec3::new(1.4, -0.75, 0.2),
+        Material::dielectric(Color::new(40, 110, 160), 0.4, 1.33),
+        false,
+    )));
+
+    // Esfera de vidrio ámbar: el centro, más grueso, es más oscuro que el borde
+    objects.push(Object::Sphere(Sphere {
+        center: Vec3::new(-1.3, -0.45, -2.2),
+        radius: 0.35,
+        material: Material::dielectric(Color::new(230, 150, 50), 0.5, 1.5),
+    }));
+
+    objects.extend(create_carved_stones(stone_texture));
+    objects.extend(create_sdf_decorations());
+
+    // Esfera brillante que va y viene: emisión con manchas de ruido, como una superficie de lava
+    let lava_sphere = Sphere {
+        center: Vec3::new(1.5, 4.0, -6.0),
+        radius: 0.5,
+        material: Material::pbr(Color::new(255, 200, 0), 0.0, 0.8).with_emissive(Color::new(255, 255, 255), 0.9).with_emissive_map(
+            ProceduralTexture::new(
+                Pattern::Noise { scale: 6.0, octaves: 3, a: Color::new(255, 120, 0), b: Color::new(255, 240, 80) },
+                PatternSpace::Object,
+            ),
+        ),
+    };
+    objects.push(Object::Moving(Moving::new(lava_sphere, Motion::Oscillate { amplitude: Vec3::new(2.5, 0.0, 0.0), frequency: 0.25 })));
+
+    objects
+}
+
+// Mundo procedural de 48x24x48 bloques de medio metro
+fn create_world(seed: u64, registry: &Rc<BlockRegistry>) -> BlockGrid {
+    WorldGenerator::new(seed).generate(Vec3::new(-12.0, -6.0, -12.0), 0.5, 48, 24, 48, registry)
+}
+
+fn create_terrain(heightmap_path: Option<&str>, grass_texture: &Texture, stone_texture: &Texture) -> Heightmap {
+    let grass = Material::with_texture(Color::new(34, 139, 34), 1.0, [0.3, 0.1], grass_texture, 0.0, 0.0);
+    let stone = Material::with_texture(Color::new(150, 150, 150), 1.0, [0.3, 0.2], stone_texture, 0.1, 0.0);
+    let min = Vec3::new(-12.0, -1.4, -16.0);
+    let size = Vec3::new(24.0, 0.8, 22.0);
+
+    match heightmap_path {
+        Some(path) => Heightmap::load_from_file(path, min, size, grass, stone),
+        None => {
+            let perlin = Perlin::new(7);
+            Heightmap::from_fn(64, min, size, grass, stone, |u, v| 0.45 + 0.5 * perlin.fbm2(u * 4.0, v * 4.0, 4, 2.0, 0.5))
+        }
+    }
+}
+
+// Bloques de piedra tallados con CSG: un muro con ventana y un cuenco
+fn create_carved_stones(stone_texture: &Texture) -> Vec<Object> {
+    let stone = Material::with_texture(Color::new(190, 190, 190), 1.0, [0.7, 0.3], stone_texture, 0.3, 0.0);
+    let block = |min: Vec3, max: Vec3| Cube::new(min, max, stone.clone(), false);
+
+    // Muro de mármol de dos bloques con una ventana cuadrada atravesándolo
+    let marble = Material::with_texture(
+        Color::new(230, 230, 225),
+        20.0,
+        [0.4, 0.3],
+        ProceduralTexture::new(
+            Pattern::Marble { scale: 4.0, turbulence: 6.0, a: Color::new(235, 235, 230), b: Color::new(90, 90, 100) },
+            PatternSpace::Object,
+        ),
+        0.3,
+        0.0,
+    );
+    let marble_block = |min: Vec3, max: Vec3| Cube::new(min, max, marble.clone(), false);
+    let wall = Csg::union(
+        marble_block(Vec3::new(-4.5, -1.0, -7.0), Vec3::new(-3.5, 1.0, -6.5)),
+        marble_block(Vec3::new(-3.5, -1.0, -7.0), Vec3::new(-2.5, 1.0, -6.5)),
+    );
+    let window = block(Vec3::new(-3.8, -0.2, -7.5), Vec3::new(-3.2, 0.4, -6.0));
+
+    // Cuenco de madera: media esfera (intersección con un bloque) vaciada por una esfera menor
+    let wood = Material::with_texture(
+        Color::new(150, 100, 50),
+        5.0,
+        [0.5, 0.1],
+        ProceduralTexture::new(
+            Pattern::Wood { scale: 6.0, rings: 1.5, a: Color::new(170, 115, 60), b: Color::new(95, 60, 30) },
+            PatternSpace::Object,
+        ),
+        0.0,
+        0.0,
+    );
+    let half_sphere = Csg::intersection(
+        Sphere { center: Vec3::new(0.8, -0.4, -2.0), radius: 0.5, material: wood.clone() },
+        block(Vec3::new(0.2, -1.0, -2.6), Vec3::new(1.4, -0.4, -1.4)),
+    );
+    let hollow = Sphere { center: Vec3::new(0.8, -0.4, -2.0), radius: 0.4, material: wood };
+
+    vec![
+        Object::Csg(Csg::difference(wall, window)),
+        Object::Csg(Csg::difference(half_sphere, hollow)),
+    ]
+}
+
+// Objetos definidos por campos de distancia: bloque redondeado, nube y adornos
+fn create_sdf_decorations() -> Vec<Object> {
+    let rounded_block = Sdf::RoundBox {
+        center: Vec3::new(2.0, -0.6, -2.5),
+        half_size: Vec3::new(0.4, 0.4, 0.4),
+        radius: 0.1,
+    };
+
+    // Nube: varias esferas fundidas con unión suave
+    let cloud = Sdf::Sphere { center: Vec3::new(-1.0, 3.5, -7.0), radius: 0.6 }
+        .smooth_union(Sdf::Sphere { center: Vec3::new(-0.3, 3.6, -7.0), radius: 0.7 }, 0.4)
+        .smooth_union(Sdf::Sphere { center: Vec3::new(0.4, 3.4, -7.2), radius: 0.5 }, 0.4)
+        .smooth_union(Sdf::Capsule { a: Vec3::new(-1.4, 3.2, -7.0), b: Vec3::new(0.8, 3.2, -7.0), radius: 0.35 }, 0.3);
+
+    // Anillo de oro con una muesca cúbica
+    let ring = Sdf::Torus { center: Vec3::new(-2.5, 0.2, -2.0), major_radius: 0.4, minor_radius: 0.12 }
+        .smooth_subtraction(Sdf::Box { center: Vec3::new(-2.1, 0.2, -2.0), half_size: Vec3::new(0.15, 0.3, 0.3) }, 0.05);
+
+    // Texturas procedurales en espacio de objeto: celdas, nubosidad y tablero
+    let cells = ProceduralTexture::new(
+        Pattern::Voronoi { scale: 8.0, a: Color::new(90, 130, 190), b: Color::new(200, 220, 240) },
+        PatternSpace::Object,
+    );
+    let fluff = ProceduralTexture::new(
+        Pattern::Noise { scale: 3.0, octaves: 4, a: Color::new(180, 185, 200), b: Color::new(255, 255, 255) },
+        PatternSpace::Object,
+    )
+    .with_seed(5);
+    let checker = ProceduralTexture::new(
+        Pattern::Checker { scale: 1.0, a: Color::new(180, 110, 180), b: Color::new(60, 30, 70) },
+        PatternSpace::Uv,
+    );
+
+    let sponge = Sdf::Menger { center: Vec3::new(4.0, 0.0, -7.0), half_size: 0.6, iterations: 3 };
+
+    // Metalicidad por casillas del tablero y rugosidad con ruido: partes pulidas y partes gastadas
+    let sponge_metal = ProceduralTexture::new(
+        Pattern::Checker { scale: 1.0, a: Color::new(255, 255, 255), b: Color::new(0, 0, 0) },
+        PatternSpace::Uv,
+    );
+    let scratches = ProceduralTexture::new(
+        Pattern::Noise { scale: 12.0, octaves: 4, a: Color::new(30, 30, 30), b: Color::new(140, 140, 140) },
+        PatternSpace::Object,
+    )
+    .with_seed(11);
+
+    vec![
+        Object::Sdf(SdfObject::new(rounded_block, Material::with_texture(Color::new(120, 160, 200), 10.0, [0.5, 0.2], cells, 0.2, 0.0))),
+        Object::Sdf(SdfObject::new(cloud, Material::with_texture(Color::new(240, 240, 250), 1.0, [0.5, 0.1], fluff, 0.0, 0.0))),
+        Object::Sdf(SdfObject::new(ring, Material::pbr(Color::new(255, 195, 85), 1.0, 0.3).with_roughness_map(scratches))),
+        Object::Sdf(
+            SdfObject::new(sponge, Material::pbr(Color::new(160, 100, 160), 0.0, 0.4).with_base_color_map(checker).with_metallic_map(sponge_metal))
+                .with_limits(256, 50.0, 5e-4),
+        ),
+    ]
 }
 
 fn fresnel_schlick(cos_theta: f32, reflectivity: f32) -> f32 {
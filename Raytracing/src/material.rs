@@ -1,14 +1,130 @@
+use nalgebra_glm::Vec3;
 use crate::color::Color;
-use crate::texture::Texture; // Asegúrate de tener el archivo `texture.rs`
+use crate::texture::{self, Texture}; // Asegúrate de tener el archivo `texture.rs`
+use crate::procedural::ProceduralTexture;
+use std::sync::atomic::{AtomicU32, Ordering};
+
+// Identificadores de material: cada material creado recibe uno nuevo y sus copias lo conservan
+static NEXT_MATERIAL_ID: AtomicU32 = AtomicU32::new(1);
+
+fn next_material_id() -> u32 {
+    NEXT_MATERIAL_ID.fetch_add(1, Ordering::Relaxed)
+}
+
+// Textura de un material: una imagen o un patrón procedural
+#[derive(Debug, Clone)]
+pub enum MaterialTexture {
+    Image(Texture),
+    Procedural(ProceduralTexture),
+}
+
+impl MaterialTexture {
+    // Color en las coordenadas UV, o en el punto `local` del objeto si el patrón es 3D
+    pub fn color_at(&self, uv: (f32, f32), local: &Vec3, lod: f32) -> Color {
+        match self {
+            MaterialTexture::Image(texture) => texture.sample(uv.0, uv.1, lod),
+            MaterialTexture::Procedural(procedural) => procedural.color_at(uv, local),
+        }
+    }
+
+    pub fn alpha_at(&self, u: f32, v: f32) -> f32 {
+        match self {
+            MaterialTexture::Image(texture) => texture.get_alpha(u, v),
+            MaterialTexture::Procedural(_) => 1.0,
+        }
+    }
+
+    // Valor escalar (0.0 a 1.0) del mapa: el canal rojo, como en los mapas en escala de grises
+    pub fn value_at(&self, uv: (f32, f32), local: &Vec3, lod: f32) -> f32 {
+        self.color_at(uv, local, lod).to_vec3().x
+    }
+
+    // Texels por unidad de UV, para elegir el nivel de mipmap
+    pub fn resolution(&self) -> f32 {
+        match self {
+            MaterialTexture::Image(texture) => texture.width as f32,
+            MaterialTexture::Procedural(_) => 1.0,
+        }
+    }
+}
+
+impl From<&Texture> for MaterialTexture {
+    fn from(texture: &Texture) -> Self {
+        MaterialTexture::Image(texture.clone())
+    }
+}
+
+impl From<ProceduralTexture> for MaterialTexture {
+    fn from(procedural: ProceduralTexture) -> Self {
+        MaterialTexture::Procedural(procedural)
+    }
+}
+
+// Parámetros del flujo metallic-roughness; el color base es `diffuse` (o su textura)
+#[derive(Debug, Clone)]
+pub struct Pbr {
+    pub metallic: f32,   // 0.0 dieléctrico, 1.0 metal
+    pub roughness: f32,  // 0.0 espejo, 1.0 completamente mate
+    pub emissive: Color,
+    pub emissive_strength: f32,
+    pub maps: Option<Box<PbrMaps>>,  // En una caja para no agrandar cada `Intersect`
+}
+
+// Mapas opcionales; cada uno, si está, sustituye al valor constante correspondiente
+#[derive(Debug, Clone, Default)]
+pub struct PbrMaps {
+    pub metallic: Option<MaterialTexture>,
+    pub roughness: Option<MaterialTexture>,
+    pub emissive: Option<MaterialTexture>,
+}
+
+impl Pbr {
+    // Resuelve los mapas en un punto de la superficie; el resultado ya no tiene mapas
+    fn at_surface(&self, uv: (f32, f32), local: &Vec3, lod: f32) -> Pbr {
+        let Some(maps) = &self.maps else {
+            return self.clone();
+        };
+        Pbr {
+            metallic: maps.metallic.as_ref().map_or(self.metallic, |map| map.value_at(uv, local, lod)),
+            roughness: maps.roughness.as_ref().map_or(self.roughness, |map| map.value_at(uv, local, lod)),
+            emissive: maps.emissive.as_ref().map_or(self.emissive, |map| map.color_at(uv, local, lod)),
+            emissive_strength: self.emissive_strength,
+            maps: None,
+        }
+    }
+
+    fn maps_mut(&mut self) -> &mut PbrMaps {
+        self.maps.get_or_insert_with(Box::default)
+    }
+}
+
+// Medio dieléctrico (agua, vidrio): refracta según su índice y absorbe la luz con la ley
+// de Beer-Lambert a medida que el rayo lo atraviesa, así el color depende del espesor
+#[derive(Debug, Clone, Copy)]
+pub struct Dielectric {
+    pub ior: f32,
+    pub absorption: Vec3,  // Coeficiente de absorción por unidad de distancia, por canal
+}
+
+impl Dielectric {
+    // Fracción de la luz, por canal, que sobrevive tras recorrer `distance` dentro del medio
+    pub fn transmittance(&self, distance: f32) -> Vec3 {
+        (-self.absorption * distance.max(0.0)).map(f32::exp)
+    }
+}
 
 #[derive(Debug, Clone)]  // Quitamos `Copy`
 pub struct Material {
     pub diffuse: Color,
     pub specular: f32,
     pub albedo: [f32; 2],
-    pub texture: Option<Texture>,  // Deja la textura como `Option`
+    pub texture: Option<MaterialTexture>,  // Deja la textura como `Option`
     pub reflectivity: f32,         // Nuevo: Nivel de reflectividad (0.0 a 1.0)
     pub transparency: f32,         // Nuevo: Nivel de transparencia (0.0 a 1.0)
+    pub alpha_cutoff: Option<f32>, // Si está, los texels con alfa menor se descartan (hojas, flores)
+    pub pbr: Option<Pbr>,          // Si está, se sombrea con Cook-Torrance en lugar de Phong
+    pub dielectric: Option<Dielectric>, // Si está, el rayo se refleja y refracta a través del medio
+    pub id: u32,                   // Identificador para el pase de ID de material; 0 es "sin material"
 }
 
 impl Material {
@@ -21,18 +137,26 @@ impl Material {
             texture: None, // Por defecto, no hay textura
             reflectivity,  // Agregamos reflectividad
             transparency,  // Agregamos transparencia
+            alpha_cutoff: None,
+            pbr: None,
+            dielectric: None,
+            id: next_material_id(),
         }
     }
 
-        // Cambia el método para aceptar una referencia en lugar de tomar la propiedad
-    pub fn with_texture(diffuse: Color, specular: f32, albedo: [f32; 2], texture: &Texture, reflectivity: f32, transparency: f32) -> Self {
+        // Acepta una referencia a una imagen o una textura procedural
+    pub fn with_texture(diffuse: Color, specular: f32, albedo: [f32; 2], texture: impl Into<MaterialTexture>, reflectivity: f32, transparency: f32) -> Self {
         Material {
             diffuse,
             specular,
             albedo,
-            texture: Some(texture.clone()), // Clonamos solo dentro de la estructura, si es necesario
+            texture: Some(texture.into()), // Clonamos solo dentro de la estructura, si es necesario
             reflectivity,
             transparency,
+            alpha_cutoff: None,
+            pbr: None,
+            dielectric: None,
+            id: next_material_id(),
         }
     }
 
@@ -46,6 +170,109 @@ impl Material {
             texture: None,
             reflectivity: 0.0, // No tiene reflectividad
             transparency: 0.0, // No es transparente
+            alpha_cutoff: None,
+            pbr: None,
+            dielectric: None,
+            id: 0,
+        }
+    }
+
+    // Material físico: color base, metalicidad y rugosidad. `specular`, `albedo`,
+    // `reflectivity` y `transparency` no se usan en el sombreado PBR.
+    pub fn pbr(base_color: Color, metallic: f32, roughness: f32) -> Self {
+        Material {
+            pbr: Some(Pbr {
+                metallic: metallic.clamp(0.0, 1.0),
+                roughness: roughness.clamp(0.0, 1.0),
+                emissive: Color::new(0, 0, 0),
+                emissive_strength: 0.0,
+                maps: None,
+            }),
+            ..Material::new(base_color, 0.0, [1.0, 0.0], 0.0, 0.0)
+        }
+    }
+
+    // Dieléctrico transparente que toma el color `tint` después de atravesar `tint_distance`
+    // unidades; a más profundidad el color se vuelve más intenso y oscuro
+    pub fn dielectric(tint: Color, tint_distance: f32, ior: f32) -> Self {
+        let absorption = tint.to_linear().map(|c| -c.max(1.0 / 255.0).ln() / tint_distance);
+        Material {
+            dielectric: Some(Dielectric { ior, absorption }),
+            ..Material::new(tint, 0.0, [0.0, 0.0], 0.0, 1.0)
+        }
+    }
+
+    pub fn with_base_color_map(mut self, texture: impl Into<MaterialTexture>) -> Self {
+        self.texture = Some(texture.into());
+        self
+    }
+
+    // Los siguientes métodos solo tienen efecto en materiales creados con `Material::pbr`
+    pub fn with_emissive(mut self, emissive: Color, strength: f32) -> Self {
+        if let Some(pbr) = &mut self.pbr {
+            pbr.emissive = emissive;
+            pbr.emissive_strength = strength;
+        }
+        self
+    }
+
+    pub fn with_metallic_map(mut self, map: impl Into<MaterialTexture>) -> Self {
+        if let Some(pbr) = &mut self.pbr {
+            pbr.maps_mut().metallic = Some(map.into());
+        }
+        self
+    }
+
+    pub fn with_roughness_map(mut self, map: impl Into<MaterialTexture>) -> Self {
+        if let Some(pbr) = &mut self.pbr {
+            pbr.maps_mut().roughness = Some(map.into());
+        }
+        self
+    }
+
+    pub fn with_emissive_map(mut self, map: impl Into<MaterialTexture>) -> Self {
+        if let Some(pbr) = &mut self.pbr {
+            pbr.maps_mut().emissive = Some(map.into());
+        }
+        self
+    }
+
+    // Activa el recorte por alfa: el rayo atraviesa los texels con alfa menor que `threshold`
+    pub fn with_alpha_cutout(mut self, threshold: f32) -> Self {
+        self.alpha_cutoff = Some(threshold);
+        self
+    }
+
+    // Indica si el punto con coordenadas UV queda recortado por la textura propia del material
+    pub fn is_cut_at(&self, u: f32, v: f32) -> bool {
+        match (&self.texture, self.alpha_cutoff) {
+            (Some(texture), Some(cutoff)) => texture.alpha_at(u, v) < cutoff,
+            _ => false,
+        }
+    }
+
+    // Material en un punto de la superficie, con el difuso y los mapas PBR tomados de sus texturas.
+    // `uv_size` es el tamaño en el mundo de una unidad de UV, `footprint` (ancho del cono del rayo
+    // en el punto) y `cos_theta` describen el rayo.
+    pub fn at_surface(&self, uv: (f32, f32), local: &Vec3, uv_size: f32, footprint: f32, cos_theta: f32) -> Material {
+        let has_pbr_maps = self.pbr.as_ref().is_some_and(|pbr| pbr.maps.is_some());
+        if self.texture.is_none() && !has_pbr_maps {
+            return self.clone();
+        }
+
+        let resolution = self.texture.as_ref().map_or(1.0, MaterialTexture::resolution);
+        let lod = texture::lod(footprint, cos_theta, resolution / uv_size);
+        Material {
+            diffuse: self.texture.as_ref().map_or(self.diffuse, |texture| texture.color_at(uv, local, lod)),
+            specular: self.specular,
+            albedo: self.albedo,
+            texture: None,
+            reflectivity: self.reflectivity,
+            transparency: self.transparency,
+            alpha_cutoff: self.alpha_cutoff,
+            pbr: self.pbr.as_ref().map(|pbr| pbr.at_surface(uv, local, lod)),
+            dielectric: self.dielectric,
+            id: self.id,
         }
     }
 }
@@ -0,0 +1,5 @@
+// Interpolación suave de Hermite: 0 por debajo de `edge0`, 1 por encima de `edge1`
+pub fn smoothstep(edge0: f32, edge1: f32, x: f32) -> f32 {
+    let t = ((x - edge0) / (edge1 - edge0)).clamp(0.0, 1.0);
+    t * t * (3.0 - 2.0 * t)
+}
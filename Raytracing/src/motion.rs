@@ -0,0 +1,56 @@
+
+use nalgebra_glm::Vec3;
+use std::f32::consts::PI;
+use crate::ray_intersect::{Intersect, Ray, RayIntersect, Span};
+
+// Trayectoria de un objeto en función del tiempo de la escena (segundos)
+#[derive(Debug, Clone, Copy)]
+pub enum Motion {
+    // Desplazamiento a velocidad constante desde la posición de partida
+    Linear { velocity: Vec3 },
+    // Vaivén sinusoidal alrededor de la posición de partida
+    Oscillate { amplitude: Vec3, frequency: f32 },
+}
+
+impl Motion {
+    pub fn offset(&self, time: f32) -> Vec3 {
+        match *self {
+            Motion::Linear { velocity } => velocity * time,
+            Motion::Oscillate { amplitude, frequency } => amplitude * (2.0 * PI * frequency * time).sin(),
+        }
+    }
+}
+
+// Envuelve cualquier sólido y lo desplaza según el tiempo que lleva el rayo: en lugar de
+// mover el objeto se mueve el rayo en sentido contrario y luego se devuelve el punto
+pub struct Moving {
+    pub object: Box<dyn RayIntersect>,
+    pub motion: Motion,
+}
+
+impl Moving {
+    pub fn new(object: impl RayIntersect + 'static, motion: Motion) -> Self {
+        Moving { object: Box::new(object), motion }
+    }
+}
+
+impl RayIntersect for Moving {
+    fn ray_intersect(&self, ray: &Ray) -> Intersect {
+        let offset = self.motion.offset(ray.time);
+        let mut hit = self.object.ray_intersect(&Ray { origin: ray.origin - offset, ..*ray });
+        if hit.is_intersecting {
+            hit.point += offset;
+        }
+        hit
+    }
+
+    fn ray_spans(&self, ray: &Ray) -> Vec<Span> {
+        let offset = self.motion.offset(ray.time);
+        let mut spans = self.object.ray_spans(&Ray { origin: ray.origin - offset, ..*ray });
+        for span in spans.iter_mut() {
+            span.enter.point += offset;
+            span.exit.point += offset;
+        }
+        spans
+    }
+}
@@ -0,0 +1,173 @@
+
+// Ruido de Perlin con semilla, para terreno y texturas procedurales
+
+#[derive(Debug, Clone)]
+pub struct Perlin {
+    perm: Vec<usize>,
+}
+
+impl Perlin {
+    pub fn new(seed: u64) -> Self {
+        // Permutación de 0..256 barajada con un xorshift sencillo para que sea reproducible
+        let mut state = seed ^ 0x9E37_79B9_7F4A_7C15;
+        let mut perm: Vec<usize> = (0..256).collect();
+        for i in (1..256).rev() {
+            state ^= state << 13;
+            state ^= state >> 7;
+            state ^= state << 17;
+            let j = (state % (i as u64 + 1)) as usize;
+            perm.swap(i, j);
+        }
+        perm.extend_from_within(..);
+        Perlin { perm }
+    }
+
+    // Ruido 2D en el rango aproximado [-1, 1]
+    pub fn noise2(&self, x: f32, y: f32) -> f32 {
+        let xi = x.floor() as i32 & 255;
+        let yi = y.floor() as i32 & 255;
+        let xf = x - x.floor();
+        let yf = y - y.floor();
+        let u = fade(xf);
+        let v = fade(yf);
+
+        let p = &self.perm;
+        let (xi, yi) = (xi as usize, yi as usize);
+        let aa = p[p[xi] + yi];
+        let ab = p[p[xi] + yi + 1];
+        let ba = p[p[xi + 1] + yi];
+        let bb = p[p[xi + 1] + yi + 1];
+
+        let x1 = lerp(grad2(aa, xf, yf), grad2(ba, xf - 1.0, yf), u);
+        let x2 = lerp(grad2(ab, xf, yf - 1.0), grad2(bb, xf - 1.0, yf - 1.0), u);
+        lerp(x1, x2, v)
+    }
+
+    // Ruido 3D en el rango aproximado [-1, 1]
+    pub fn noise3(&self, x: f32, y: f32, z: f32) -> f32 {
+        let xi = (x.floor() as i32 & 255) as usize;
+        let yi = (y.floor() as i32 & 255) as usize;
+        let zi = (z.floor() as i32 & 255) as usize;
+        let xf = x - x.floor();
+        let yf = y - y.floor();
+        let zf = z - z.floor();
+        let u = fade(xf);
+        let v = fade(yf);
+        let w = fade(zf);
+
+        let p = &self.perm;
+        let a = p[xi] + yi;
+        let aa = p[a] + zi;
+        let ab = p[a + 1] + zi;
+        let b = p[xi + 1] + yi;
+        let ba = p[b] + zi;
+        let bb = p[b + 1] + zi;
+
+        lerp(
+            lerp(
+                lerp(grad3(p[aa], xf, yf, zf), grad3(p[ba], xf - 1.0, yf, zf), u),
+                lerp(grad3(p[ab], xf, yf - 1.0, zf), grad3(p[bb], xf - 1.0, yf - 1.0, zf), u),
+                v,
+            ),
+            lerp(
+                lerp(grad3(p[aa + 1], xf, yf, zf - 1.0), grad3(p[ba + 1], xf - 1.0, yf, zf - 1.0), u),
+                lerp(grad3(p[ab + 1], xf, yf - 1.0, zf - 1.0), grad3(p[bb + 1], xf - 1.0, yf - 1.0, zf - 1.0), u),
+                v,
+            ),
+            w,
+        )
+    }
+
+    // Suma de octavas (fBm) de ruido 3D
+    pub fn fbm3(&self, x: f32, y: f32, z: f32, octaves: u32) -> f32 {
+        let (mut sum, mut amplitude, mut frequency, mut norm) = (0.0, 1.0, 1.0, 0.0);
+        for _ in 0..octaves {
+            sum += amplitude * self.noise3(x * frequency, y * frequency, z * frequency);
+            norm += amplitude;
+            amplitude *= 0.5;
+            frequency *= 2.0;
+        }
+        sum / norm
+    }
+
+    // Turbulencia: suma de octavas del valor absoluto del ruido, en [0, ~1]
+    pub fn turbulence(&self, x: f32, y: f32, z: f32, octaves: u32) -> f32 {
+        let (mut sum, mut amplitude, mut frequency) = (0.0, 1.0, 1.0);
+        for _ in 0..octaves {
+            sum += amplitude * self.noise3(x * frequency, y * frequency, z * frequency).abs();
+            amplitude *= 0.5;
+            frequency *= 2.0;
+        }
+        sum
+    }
+
+    // Ruido celular (Voronoi): distancia al punto característico más cercano, en [0, ~1]
+    pub fn voronoi3(&self, x: f32, y: f32, z: f32) -> f32 {
+        let (cx, cy, cz) = (x.floor() as i32, y.floor() as i32, z.floor() as i32);
+        let mut nearest = f32::INFINITY;
+        for dz in -1..=1 {
+            for dy in -1..=1 {
+                for dx in -1..=1 {
+                    let (i, j, k) = (cx + dx, cy + dy, cz + dz);
+                    // Un punto característico por celda, en una posición pseudoaleatoria fija
+                    let fx = i as f32 + self.hash3(i, j, k);
+                    let fy = j as f32 + self.hash3(j, k, i);
+                    let fz = k as f32 + self.hash3(k, i, j);
+                    let d = ((x - fx).powi(2) + (y - fy).powi(2) + (z - fz).powi(2)).sqrt();
+                    nearest = nearest.min(d);
+                }
+            }
+        }
+        nearest
+    }
+
+    // Valor pseudoaleatorio en [0, 1) para una celda entera
+    fn hash3(&self, i: i32, j: i32, k: i32) -> f32 {
+        let p = &self.perm;
+        p[p[p[(i & 255) as usize] + (j & 255) as usize] + (k & 255) as usize] as f32 / 256.0
+    }
+
+    // Suma de octavas (fBm) de ruido 2D
+    pub fn fbm2(&self, x: f32, y: f32, octaves: u32, lacunarity: f32, gain: f32) -> f32 {
+        let mut sum = 0.0;
+        let mut amplitude = 1.0;
+        let mut frequency = 1.0;
+        let mut norm = 0.0;
+        for _ in 0..octaves {
+            sum += amplitude * self.noise2(x * frequency, y * frequency);
+            norm += amplitude;
+            amplitude *= gain;
+            frequency *= lacunarity;
+        }
+        sum / norm
+    }
+}
+
+fn fade(t: f32) -> f32 {
+    t * t * t * (t * (t * 6.0 - 15.0) + 10.0)
+}
+
+fn lerp(a: f32, b: f32, t: f32) -> f32 {
+    a + t * (b - a)
+}
+
+fn grad2(hash: usize, x: f32, y: f32) -> f32 {
+    match hash & 7 {
+        0 => x + y,
+        1 => -x + y,
+        2 => x - y,
+        3 => -x - y,
+        4 => x,
+        5 => -x,
+        6 => y,
+        _ => -y,
+    }
+}
+
+fn grad3(hash: usize, x: f32, y: f32, z: f32) -> f32 {
+    // Los 12 gradientes de las aristas del cubo (Perlin mejorado)
+    let h = hash & 15;
+    let u = if h < 8 { x } else { y };
+    let v = if h < 4 { y } else if h == 12 || h == 14 { x } else { z };
+    (if h & 1 == 0 { u } else { -u }) + (if h & 2 == 0 { v } else { -v })
+}
@@ -0,0 +1,46 @@
+
+use nalgebra_glm::Vec3;
+use crate::sampling;
+
+// De dónde sale la oclusión ambiental
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum OcclusionMode {
+    // Rayos por el hemisferio de la normal que buscan geometría cercana
+    Hemisphere,
+    // Por vértice a partir de los bloques vecinos, como en Minecraft; solo en mundos de bloques
+    Voxel,
+    // Las dos multiplicadas
+    Both,
+}
+
+// Oclusión ambiental: cuánto del entorno inmediato de un punto está tapado por geometría
+// cercana. Atenúa una luz ambiente constante que llega a todas las superficies.
+#[derive(Debug, Clone, Copy)]
+pub struct AmbientOcclusion {
+    pub mode: OcclusionMode,
+    pub samples: u32,   // Rayos por punto en el hemisferio
+    pub distance: f32,  // Lo que esté más lejos no tapa
+    pub ambient: Vec3,  // Luz ambiente sin tapar
+}
+
+impl Default for AmbientOcclusion {
+    fn default() -> Self {
+        AmbientOcclusion { mode: OcclusionMode::Hemisphere, samples: 16, distance: 1.0, ambient: Vec3::new(0.15, 0.15, 0.15) }
+    }
+}
+
+impl AmbientOcclusion {
+    pub fn uses_hemisphere(&self) -> bool {
+        self.mode != OcclusionMode::Voxel && self.samples > 0
+    }
+
+    pub fn uses_voxels(&self) -> bool {
+        self.mode != OcclusionMode::Hemisphere
+    }
+
+    // Direcciones de los rayos por el hemisferio de `normal`, con el patrón girado según `point`
+    // para cambiar bandas por ruido
+    pub fn directions(&self, point: &Vec3, normal: &Vec3) -> Vec<Vec3> {
+        sampling::fibonacci_hemisphere(self.samples, normal, sampling::hash2(sampling::seed(point)))
+    }
+}
@@ -0,0 +1,206 @@
+
+use nalgebra_glm::{Vec2, Vec3};
+use std::f32::consts::FRAC_1_SQRT_2;
+use crate::framebuffer::Framebuffer;
+
+// Resplandor alrededor de lo que pasa del umbral: la parte sobrante se desenfoca con
+// gaussianas de radios crecientes y se suma a la imagen, como la dispersión dentro de la lente
+#[derive(Debug, Clone, Copy)]
+pub struct Bloom {
+    pub enabled: bool,
+    pub threshold: f32,  // Radiancia a partir de la cual algo brilla
+    pub intensity: f32,
+    pub radius: f32,     // Desviación de la gaussiana más pequeña, en fracción del alto de la imagen
+    pub scales: u32,     // Gaussianas, cada una del doble de radio que la anterior
+}
+
+// Destellos de la lente: imágenes fantasma de las zonas brillantes reflejadas a través del
+// centro de la imagen y un halo, con un poco de aberración cromática
+#[derive(Debug, Clone, Copy)]
+pub struct LensFlare {
+    pub enabled: bool,
+    pub ghosts: u32,
+    pub dispersal: f32,   // Separación entre fantasmas, en fracción de la distancia al centro
+    pub halo_width: f32,  // Radio del halo, en fracción de la imagen
+    pub chromatic: f32,   // Desplazamiento de los canales rojo y azul, en fracción de la imagen
+    pub intensity: f32,
+}
+
+// Oscurecimiento de las esquinas
+#[derive(Debug, Clone, Copy)]
+pub struct Vignette {
+    pub enabled: bool,
+    pub strength: f32,  // Cuánto se oscurecen las esquinas: 0 nada, 1 negro
+    pub falloff: f32,   // Exponente sobre la distancia al centro: más alto, más concentrado en las esquinas
+}
+
+// Efectos de cámara que se aplican a la radiancia antes de pasarla a 8 bits, en este orden:
+// resplandor y destellos a partir de la imagen original, y luego el viñeteado sobre todo
+#[derive(Debug, Clone, Copy)]
+pub struct PostProcess {
+    pub bloom: Bloom,
+    pub flare: LensFlare,
+    pub vignette: Vignette,
+}
+
+impl Default for PostProcess {
+    fn default() -> Self {
+        PostProcess {
+            bloom: Bloom { enabled: false, threshold: 1.0, intensity: 0.6, radius: 0.01, scales: 4 },
+            flare: LensFlare { enabled: false, ghosts: 5, dispersal: 0.3, halo_width: 0.45, chromatic: 0.01, intensity: 0.05 },
+            vignette: Vignette { enabled: false, strength: 0.4, falloff: 2.5 },
+        }
+    }
+}
+
+impl PostProcess {
+    pub fn is_enabled(&self) -> bool {
+        self.bloom.enabled || self.flare.enabled || self.vignette.enabled
+    }
+
+    // Aplica los efectos activos sobre la radiancia del framebuffer
+    pub fn apply(&self, framebuffer: &mut Framebuffer) {
+        if !self.is_enabled() {
+            return;
+        }
+        let (width, height) = (framebuffer.width, framebuffer.height);
+
+        // Solo lo que sobra del umbral, conservando el tono
+        let bright: Vec<Vec3> = framebuffer
+            .radiance
+            .iter()
+            .map(|color| {
+                let luminance = 0.2126 * color.x + 0.7152 * color.y + 0.0722 * color.z;
+                if luminance <= self.bloom.threshold { Vec3::zeros() } else { color * ((luminance - self.bloom.threshold) / luminance) }
+            })
+            .collect();
+
+        let mut glow = vec![Vec3::zeros(); width * height];
+        if self.bloom.enabled {
+            let scales = self.bloom.scales.max(1);
+            for scale in 0..scales {
+                let sigma = self.bloom.radius * height as f32 * (1 << scale) as f32;
+                let blurred = gaussian_blur(&bright, width, height, sigma);
+                for (pixel, value) in glow.iter_mut().zip(blurred) {
+                    *pixel += value * (self.bloom.intensity / scales as f32);
+                }
+            }
+        }
+
+        if self.flare.enabled {
+            let flare = self.flare.render(&bright, width, height);
+            let flare = gaussian_blur(&flare, width, height, 0.005 * height as f32);
+            for (pixel, value) in glow.iter_mut().zip(flare) {
+                *pixel += value * self.flare.intensity;
+            }
+        }
+
+        for (i, (radiance, glow)) in framebuffer.radiance.iter_mut().zip(glow).enumerate() {
+            *radiance += glow;
+            if self.vignette.enabled {
+                *radiance *= self.vignette.factor(i % width, i / width, width, height);
+            }
+        }
+    }
+}
+
+impl LensFlare {
+    // Fantasmas y halo de la imagen brillante: cada píxel mira hacia el centro a través de su
+    // reflejo, donde caen las imágenes de las fuentes del lado opuesto
+    fn render(&self, bright: &[Vec3], width: usize, height: usize) -> Vec<Vec3> {
+        let center = Vec2::new(0.5, 0.5);
+        let mut flare = vec![Vec3::zeros(); width * height];
+        for y in 0..height {
+            for x in 0..width {
+                let uv = Vec2::new(1.0 - (x as f32 + 0.5) / width as f32, 1.0 - (y as f32 + 0.5) / height as f32);
+                let ghost_step = (center - uv) * self.dispersal;
+                let mut color = Vec3::zeros();
+
+                // Los fantasmas más alejados del centro se desvanecen, y los que amplían la fuente
+                // reparten su luz en más superficie: el peso baja con el cuadrado del aumento
+                for i in 0..self.ghosts {
+                    let offset = uv + ghost_step * i as f32;
+                    let scale = 1.0 - self.dispersal * i as f32;
+                    let weight = (1.0 - (center - offset).norm() / FRAC_1_SQRT_2).max(0.0).powi(10) * scale * scale;
+                    color += self.chromatic_sample(bright, width, height, offset, &ghost_step) * weight;
+                }
+
+                if let Some(direction) = ghost_step.try_normalize(1e-6) {
+                    let offset = uv + direction * self.halo_width;
+                    let weight = (1.0 - (center - offset).norm() / FRAC_1_SQRT_2).max(0.0).powi(5) * 0.15;
+                    color += self.chromatic_sample(bright, width, height, offset, &direction) * weight;
+                }
+                flare[y * width + x] = color;
+            }
+        }
+        flare
+    }
+
+    // Muestra con los canales rojo y azul desplazados en sentidos opuestos a lo largo de `direction`
+    fn chromatic_sample(&self, image: &[Vec3], width: usize, height: usize, uv: Vec2, direction: &Vec2) -> Vec3 {
+        let shift = direction.try_normalize(1e-6).unwrap_or_else(Vec2::zeros) * self.chromatic;
+        Vec3::new(
+            bilinear(image, width, height, uv - shift).x,
+            bilinear(image, width, height, uv).y,
+            bilinear(image, width, height, uv + shift).z,
+        )
+    }
+}
+
+impl Vignette {
+    fn factor(&self, x: usize, y: usize, width: usize, height: usize) -> f32 {
+        // Distancia al centro normalizada para que las esquinas estén a 1
+        let dx = (x as f32 + 0.5) / width as f32 * 2.0 - 1.0;
+        let dy = (y as f32 + 0.5) / height as f32 * 2.0 - 1.0;
+        let distance = ((dx * dx + dy * dy) / 2.0).sqrt();
+        (1.0 - self.strength * distance.powf(self.falloff)).clamp(0.0, 1.0)
+    }
+}
+
+// Desenfoque gaussiano separable; fuera de la imagen se repite el borde
+fn gaussian_blur(image: &[Vec3], width: usize, height: usize, sigma: f32) -> Vec<Vec3> {
+    if sigma < 0.5 {
+        return image.to_vec();
+    }
+    let radius = (3.0 * sigma).ceil() as isize;
+    let mut kernel: Vec<f32> = (-radius..=radius).map(|i| (-(i * i) as f32 / (2.0 * sigma * sigma)).exp()).collect();
+    let total: f32 = kernel.iter().sum();
+    kernel.iter_mut().for_each(|weight| *weight /= total);
+
+    let pass = |input: &[Vec3], step_x: isize, step_y: isize| -> Vec<Vec3> {
+        let mut output = vec![Vec3::zeros(); width * height];
+        for y in 0..height as isize {
+            for x in 0..width as isize {
+                let mut sum = Vec3::zeros();
+                for (k, weight) in kernel.iter().enumerate() {
+                    let offset = k as isize - radius;
+                    let sx = (x + offset * step_x).clamp(0, width as isize - 1);
+                    let sy = (y + offset * step_y).clamp(0, height as isize - 1);
+                    sum += input[(sy * width as isize + sx) as usize] * *weight;
+                }
+                output[(y * width as isize + x) as usize] = sum;
+            }
+        }
+        output
+    };
+    let horizontal = pass(image, 1, 0);
+    pass(&horizontal, 0, 1)
+}
+
+// Interpolación bilineal en coordenadas 0..1; fuera de la imagen es negro
+fn bilinear(image: &[Vec3], width: usize, height: usize, uv: Vec2) -> Vec3 {
+    let x = uv.x * width as f32 - 0.5;
+    let y = uv.y * height as f32 - 0.5;
+    let (x0, y0) = (x.floor(), y.floor());
+    let (tx, ty) = (x - x0, y - y0);
+    let texel = |x: f32, y: f32| {
+        if x < 0.0 || y < 0.0 || x >= width as f32 || y >= height as f32 {
+            Vec3::zeros()
+        } else {
+            image[y as usize * width + x as usize]
+        }
+    };
+    let top = texel(x0, y0) * (1.0 - tx) + texel(x0 + 1.0, y0) * tx;
+    let bottom = texel(x0, y0 + 1.0) * (1.0 - tx) + texel(x0 + 1.0, y0 + 1.0) * tx;
+    top * (1.0 - ty) + bottom * ty
+}
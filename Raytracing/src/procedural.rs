@@ -0,0 +1,82 @@
+
+use nalgebra_glm::Vec3;
+use crate::color::Color;
+use crate::noise::Perlin;
+
+// Patrones procedurales; cada uno mezcla los colores `a` y `b`
+#[derive(Debug, Clone)]
+pub enum Pattern {
+    Checker { scale: f32, a: Color, b: Color },
+    Noise { scale: f32, octaves: u32, a: Color, b: Color },
+    // Vetas de mármol: seno perturbado por turbulencia
+    Marble { scale: f32, turbulence: f32, a: Color, b: Color },
+    // Anillos concéntricos alrededor del eje y, con algo de ruido
+    Wood { scale: f32, rings: f32, a: Color, b: Color },
+    Voronoi { scale: f32, a: Color, b: Color },
+}
+
+// Dónde se evalúa el patrón: en las coordenadas UV de la superficie o en el espacio del objeto
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PatternSpace {
+    Uv,
+    Object,
+}
+
+#[derive(Debug, Clone)]
+pub struct ProceduralTexture {
+    pub pattern: Pattern,
+    pub space: PatternSpace,
+    noise: Perlin,
+}
+
+impl ProceduralTexture {
+    pub fn new(pattern: Pattern, space: PatternSpace) -> Self {
+        ProceduralTexture { pattern, space, noise: Perlin::new(0) }
+    }
+
+    pub fn with_seed(mut self, seed: u64) -> Self {
+        self.noise = Perlin::new(seed);
+        self
+    }
+
+    // Color en las coordenadas UV `(u, v)` o en el punto `local` (relativo al objeto)
+    pub fn color_at(&self, (u, v): (f32, f32), local: &Vec3) -> Color {
+        let p = match self.space {
+            PatternSpace::Uv => Vec3::new(u, v, 0.0),
+            PatternSpace::Object => *local,
+        };
+
+        match &self.pattern {
+            Pattern::Checker { scale, a, b } => {
+                let q = p * *scale;
+                let parity = (q.x.floor() + q.y.floor() + q.z.floor()) as i64;
+                if parity.rem_euclid(2) == 0 { *a } else { *b }
+            }
+            Pattern::Noise { scale, octaves, a, b } => {
+                let q = p * *scale;
+                let t = 0.5 + 0.5 * self.noise.fbm3(q.x, q.y, q.z, *octaves);
+                mix(*a, *b, t)
+            }
+            Pattern::Marble { scale, turbulence, a, b } => {
+                let q = p * *scale;
+                let t = 0.5 + 0.5 * (q.x + q.y + turbulence * self.noise.turbulence(q.x, q.y, q.z, 5)).sin();
+                mix(*a, *b, t)
+            }
+            Pattern::Wood { scale, rings, a, b } => {
+                let q = p * *scale;
+                let radius = (q.x * q.x + q.z * q.z).sqrt() + 0.3 * self.noise.noise3(q.x, q.y * 0.2, q.z);
+                let t = (radius * rings).fract();
+                mix(*a, *b, t * t)
+            }
+            Pattern::Voronoi { scale, a, b } => {
+                let q = p * *scale;
+                mix(*a, *b, self.noise.voronoi3(q.x, q.y, q.z).min(1.0))
+            }
+        }
+    }
+}
+
+fn mix(a: Color, b: Color, t: f32) -> Color {
+    let t = t.clamp(0.0, 1.0);
+    a * (1.0 - t) + b * t
+}
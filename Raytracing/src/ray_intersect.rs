@@ -3,6 +3,42 @@ use nalgebra_glm::Vec3;
 use crate::material::Material;
 use crate::cube::Cube;  // Import the Cube
 
+// Rayo con el instante (segundos de la escena) en que viaja; `render` lo fija en cada
+// muestra del obturador y los rayos secundarios lo heredan con `spawn`.
+// Lleva también el cono que cubre el píxel del que sale, para elegir el nivel de mipmap:
+// su ancho en el origen y cuánto se abre por unidad de distancia.
+#[derive(Debug, Clone, Copy)]
+pub struct Ray {
+    pub origin: Vec3,
+    pub direction: Vec3,
+    pub time: f32,
+    pub width: f32,
+    pub spread: f32,
+}
+
+impl Ray {
+    pub fn new(origin: Vec3, direction: Vec3, time: f32) -> Self {
+        Ray { origin, direction, time, width: 0.0, spread: 0.0 }
+    }
+
+    pub fn with_spread(mut self, spread: f32) -> Self {
+        self.spread = spread;
+        self
+    }
+
+    // Ancho del cono a `distance` unidades de mundo del origen
+    pub fn footprint(&self, distance: f32) -> f32 {
+        self.width + self.spread * distance
+    }
+
+    // Rayo secundario desde `origin` hacia `direction` en el mismo instante. El cono sigue
+    // abriéndose igual desde el ancho que tenía al llegar, como en un reflejo plano.
+    pub fn spawn(&self, origin: Vec3, direction: Vec3) -> Ray {
+        let width = self.footprint((origin - self.origin).magnitude());
+        Ray { origin, direction, width, ..*self }
+    }
+}
+
 #[derive(Debug, Clone)] 
 pub struct Intersect {
     pub point: Vec3,
@@ -35,12 +71,46 @@ impl Intersect {
     }
 }
 
+// Tramo del rayo que queda dentro de un sólido, con la entrada y la salida
+#[derive(Debug, Clone)]
+pub struct Span {
+    pub enter: Intersect,
+    pub exit: Intersect,
+}
+
+// Primer borde de los tramos por delante del origen que no quede recortado por el alfa.
+// Las salidas se devuelven con la normal invertida porque se ven desde dentro del sólido.
+pub fn first_opaque(spans: Vec<Span>, is_cut: impl Fn(&Intersect) -> bool) -> Intersect {
+    spans
+        .into_iter()
+        .flat_map(|span| {
+            let mut exit = span.exit;
+            exit.normal = -exit.normal;
+            [span.enter, exit]
+        })
+        .find(|hit| hit.distance > 1e-4 && !is_cut(hit))
+        .unwrap_or_else(Intersect::empty)
+}
+
 pub trait RayIntersect {
-    fn ray_intersect(&self, ray_origin: &Vec3, ray_direction: &Vec3) -> Intersect;
+    fn ray_intersect(&self, ray: &Ray) -> Intersect;
+
+    // Todos los tramos de entrada/salida a lo largo del rayo, ordenados por distancia.
+    // Las distancias pueden ser negativas si el origen del rayo está dentro del sólido.
+    fn ray_spans(&self, ray: &Ray) -> Vec<Span>;
 }
 
 impl RayIntersect for Cube {
-    fn ray_intersect(&self, ray_origin: &Vec3, ray_direction: &Vec3) -> Intersect {
+    fn ray_intersect(&self, ray: &Ray) -> Intersect {
+        // Con recorte por alfa el rayo puede atravesar la cara frontal y ver la trasera
+        if self.material.alpha_cutoff.is_some() {
+            return first_opaque(self.ray_spans(ray), |hit| {
+                let (u, v) = self.get_uv(&hit.point);
+                self.material.is_cut_at(u, v)
+            });
+        }
+
+        let (ray_origin, ray_direction) = (&ray.origin, &ray.direction);
         let inv_dir = Vec3::new(1.0, 1.0, 1.0).component_div(ray_direction); // Invertir la dirección del rayo para simplificar los cálculos
 
         // Limites del cubo (min y max)
@@ -59,10 +129,35 @@ impl RayIntersect for Cube {
         if t_near < t_far && t_far > 0.0 {
             let point = ray_origin + ray_direction * t_near;  // Asegura que point es un Vec3
             let normal = self.get_normal(&point);  // Calcula la normal
-            return Intersect::new(point, normal, t_near, self.material.clone());  // Clona el material
+            return self.surface_hit(point, normal, t_near, ray);  // Evalúa el material en el punto
         }
         
 
         Intersect::empty()
     }
+
+    fn ray_spans(&self, ray: &Ray) -> Vec<Span> {
+        let (ray_origin, ray_direction) = (&ray.origin, &ray.direction);
+        let inv_dir = Vec3::new(1.0, 1.0, 1.0).component_div(ray_direction);
+
+        let tmin = (self.min - ray_origin).component_mul(&inv_dir);
+        let tmax = (self.max - ray_origin).component_mul(&inv_dir);
+
+        let t1 = Vec3::new(tmin.x.min(tmax.x), tmin.y.min(tmax.y), tmin.z.min(tmax.z));
+        let t2 = Vec3::new(tmin.x.max(tmax.x), tmin.y.max(tmax.y), tmin.z.max(tmax.z));
+
+        let t_near = t1.x.max(t1.y).max(t1.z);
+        let t_far = t2.x.min(t2.y).min(t2.z);
+
+        if t_near >= t_far {
+            return Vec::new();
+        }
+
+        let near_point = ray_origin + ray_direction * t_near;
+        let far_point = ray_origin + ray_direction * t_far;
+        vec![Span {
+            enter: self.surface_hit(near_point, self.get_normal(&near_point), t_near, ray),
+            exit: self.surface_hit(far_point, self.get_normal(&far_point), t_far, ray),
+        }]
+    }
 }
@@ -0,0 +1,42 @@
+
+use nalgebra_glm::Vec3;
+use std::f32::consts::PI;
+
+// Valor pseudoaleatorio fijo en 0..1 para `seed`; el mismo valor da siempre el mismo resultado
+pub fn hash(seed: f32) -> f32 {
+    (seed.sin() * 43758.547).fract().abs()
+}
+
+// Dos valores pseudoaleatorios en 0..1 para `seed`, sin relación entre sí
+pub fn hash2(seed: f32) -> (f32, f32) {
+    (hash(seed), ((seed + 1.0).sin() * 24634.635).fract().abs())
+}
+
+// Semilla para `hash` a partir de un punto o una dirección
+pub fn seed(point: &Vec3) -> f32 {
+    point.x * 12.9898 + point.y * 78.233 + point.z * 37.719
+}
+
+// Punto `i` de los `n` de una red de Fibonacci en el cuadrado 0..1, desplazada por `shift`
+// (módulo 1) para cambiar el patrón repetido por ruido fino
+pub fn fibonacci(i: u32, n: u32, shift: (f32, f32)) -> (f32, f32) {
+    (((i as f32 + 0.5) / n as f32 + shift.0).fract(), (i as f32 * 0.618034 + shift.1).fract())
+}
+
+// `n` direcciones de la red de Fibonacci repartidas por el hemisferio de `normal` con densidad
+// proporcional al coseno, así cada una pesa lo mismo
+pub fn fibonacci_hemisphere(n: u32, normal: &Vec3, shift: (f32, f32)) -> Vec<Vec3> {
+    // Base ortonormal alrededor de la normal
+    let helper = if normal.x.abs() > 0.9 { Vec3::new(0.0, 1.0, 0.0) } else { Vec3::new(1.0, 0.0, 0.0) };
+    let tangent = normal.cross(&helper).normalize();
+    let bitangent = normal.cross(&tangent);
+
+    (0..n)
+        .map(|i| {
+            let (u, v) = fibonacci(i, n, shift);
+            let radius = u.sqrt();
+            let angle = 2.0 * PI * v;
+            tangent * (radius * angle.cos()) + bitangent * (radius * angle.sin()) + normal * (1.0 - u).sqrt()
+        })
+        .collect()
+}
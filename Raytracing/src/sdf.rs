@@ -0,0 +1,257 @@
+
+use nalgebra_glm::Vec3;
+use crate::material::Material;
+use crate::ray_intersect::{Intersect, Ray, RayIntersect, Span};
+
+// Primitivas y combinadores de campos de distancia con signo
+#[derive(Debug, Clone)]
+pub enum Sdf {
+    Sphere { center: Vec3, radius: f32 },
+    Box { center: Vec3, half_size: Vec3 },
+    RoundBox { center: Vec3, half_size: Vec3, radius: f32 },
+    Torus { center: Vec3, major_radius: f32, minor_radius: f32 },
+    Capsule { a: Vec3, b: Vec3, radius: f32 },
+    // Esponja de Menger dentro de un cubo, para decoraciones fractales
+    Menger { center: Vec3, half_size: f32, iterations: u32 },
+    SmoothUnion(Box<Sdf>, Box<Sdf>, f32),
+    SmoothSubtraction(Box<Sdf>, Box<Sdf>, f32),
+}
+
+impl Sdf {
+    pub fn smooth_union(self, other: Sdf, k: f32) -> Sdf {
+        Sdf::SmoothUnion(Box::new(self), Box::new(other), k)
+    }
+
+    // Resta `other` de `self` suavizando el borde con el radio `k`
+    pub fn smooth_subtraction(self, other: Sdf, k: f32) -> Sdf {
+        Sdf::SmoothSubtraction(Box::new(self), Box::new(other), k)
+    }
+
+    pub fn distance(&self, p: &Vec3) -> f32 {
+        match self {
+            Sdf::Sphere { center, radius } => (p - center).magnitude() - radius,
+            Sdf::Box { center, half_size } => box_distance(&(p - center), half_size),
+            Sdf::RoundBox { center, half_size, radius } => {
+                box_distance(&(p - center), &half_size.add_scalar(-radius)) - radius
+            }
+            Sdf::Torus { center, major_radius, minor_radius } => {
+                let q = p - center;
+                let ring = (q.x * q.x + q.z * q.z).sqrt() - major_radius;
+                (ring * ring + q.y * q.y).sqrt() - minor_radius
+            }
+            Sdf::Capsule { a, b, radius } => {
+                let pa = p - a;
+                let ba = b - a;
+                let h = (pa.dot(&ba) / ba.dot(&ba)).clamp(0.0, 1.0);
+                (pa - ba * h).magnitude() - radius
+            }
+            Sdf::Menger { center, half_size, iterations } => menger_distance(&((p - center) / *half_size), *iterations) * half_size,
+            Sdf::SmoothUnion(a, b, k) => {
+                let d1 = a.distance(p);
+                let d2 = b.distance(p);
+                let h = (0.5 + 0.5 * (d2 - d1) / k).clamp(0.0, 1.0);
+                mix(d2, d1, h) - k * h * (1.0 - h)
+            }
+            Sdf::SmoothSubtraction(a, b, k) => {
+                let d1 = a.distance(p);
+                let d2 = b.distance(p);
+                let h = (0.5 - 0.5 * (d1 + d2) / k).clamp(0.0, 1.0);
+                mix(d1, -d2, h) + k * h * (1.0 - h)
+            }
+        }
+    }
+}
+
+fn mix(a: f32, b: f32, t: f32) -> f32 {
+    a * (1.0 - t) + b * t
+}
+
+fn box_distance(p: &Vec3, half_size: &Vec3) -> f32 {
+    let q = p.abs() - half_size;
+    let outside = Vec3::new(q.x.max(0.0), q.y.max(0.0), q.z.max(0.0)).magnitude();
+    let inside = q.x.max(q.y).max(q.z).min(0.0);
+    outside + inside
+}
+
+// Distancia a una esponja de Menger de lado 2 centrada en el origen
+fn menger_distance(p: &Vec3, iterations: u32) -> f32 {
+    let mut d = box_distance(p, &Vec3::new(1.0, 1.0, 1.0));
+    let mut scale = 1.0;
+    for _ in 0..iterations {
+        let a = (p * scale).map(|c| (c.rem_euclid(2.0)) - 1.0);
+        scale *= 3.0;
+        let r = (Vec3::new(1.0, 1.0, 1.0) - 3.0 * a.abs()).abs();
+        let da = r.x.max(r.y);
+        let db = r.y.max(r.z);
+        let dc = r.z.max(r.x);
+        let c = (da.min(db).min(dc) - 1.0) / scale;
+        d = d.max(c);
+    }
+    d
+}
+
+// Objeto definido por un SDF, intersectado mediante sphere tracing
+#[derive(Debug, Clone)]
+pub struct SdfObject {
+    pub shape: Sdf,
+    pub material: Material,
+    pub max_steps: u32,      // Pasos máximos de la marcha por rayo
+    pub max_distance: f32,   // Distancia máxima recorrida antes de rendirse
+    pub epsilon: f32,        // Umbral para considerar que tocamos la superficie
+}
+
+impl SdfObject {
+    pub fn new(shape: Sdf, material: Material) -> Self {
+        SdfObject {
+            shape,
+            material,
+            max_steps: 128,
+            max_distance: 100.0,
+            epsilon: 1e-3,
+        }
+    }
+
+    pub fn with_limits(mut self, max_steps: u32, max_distance: f32, epsilon: f32) -> Self {
+        self.max_steps = max_steps;
+        self.max_distance = max_distance;
+        self.epsilon = epsilon;
+        self
+    }
+
+    // Normal estimada con el gradiente por diferencias centrales
+    pub fn get_normal(&self, point: &Vec3) -> Vec3 {
+        let h = self.epsilon;
+        let dx = Vec3::new(h, 0.0, 0.0);
+        let dy = Vec3::new(0.0, h, 0.0);
+        let dz = Vec3::new(0.0, 0.0, h);
+        Vec3::new(
+            self.shape.distance(&(point + dx)) - self.shape.distance(&(point - dx)),
+            self.shape.distance(&(point + dy)) - self.shape.distance(&(point - dy)),
+            self.shape.distance(&(point + dz)) - self.shape.distance(&(point - dz)),
+        )
+        .normalize()
+    }
+
+    // Avanza desde `t` hasta que el signo del campo cambia; `inside` indica de qué lado partimos
+    fn march(&self, origin: &Vec3, direction: &Vec3, mut t: f32, inside: bool) -> Option<f32> {
+        for _ in 0..self.max_steps {
+            let d = self.shape.distance(&(origin + direction * t));
+            let d = if inside { -d } else { d };
+            if d < self.epsilon {
+                return Some(t);
+            }
+            t += d;
+            if t > self.max_distance {
+                break;
+            }
+        }
+        None
+    }
+
+    // `direction` es la del rayo normalizada, así que `t` está en unidades de mundo
+    fn hit(&self, ray: &Ray, direction: &Vec3, t: f32, scale: f32) -> Intersect {
+        let point = ray.origin + direction * t;
+        let normal = self.get_normal(&point);
+        // Sin parametrización natural: UV proyectadas desde arriba y patrones 3D en coordenadas de mundo
+        let material = self.material.at_surface((point.x, point.z), &point, 1.0, ray.footprint(t), normal.dot(direction));
+        Intersect::new(point, normal, t * scale, material)
+    }
+}
+
+impl RayIntersect for SdfObject {
+    fn ray_intersect(&self, ray: &Ray) -> Intersect {
+        let (ray_origin, ray_direction) = (&ray.origin, &ray.direction);
+        let scale = 1.0 / ray_direction.magnitude();
+        let direction = ray_direction * scale;
+        match self.march(ray_origin, &direction, 0.0, false) {
+            Some(t) if t > 0.0 => self.hit(ray, &direction, t, scale),
+            _ => Intersect::empty(),
+        }
+    }
+
+    fn ray_spans(&self, ray: &Ray) -> Vec<Span> {
+        let (ray_origin, ray_direction) = (&ray.origin, &ray.direction);
+        let scale = 1.0 / ray_direction.magnitude();
+        let direction = ray_direction * scale;
+        let mut spans = Vec::new();
+
+        // Si el origen ya está dentro, el tramo empieza en el propio origen
+        let mut t = 0.0;
+        let mut enter = if self.shape.distance(ray_origin) < 0.0 {
+            Some(0.0)
+        } else {
+            self.march(ray_origin, &direction, t, false)
+        };
+
+        while let Some(t_enter) = enter {
+            let Some(t_exit) = self.march(ray_origin, &direction, t_enter + 2.0 * self.epsilon, true) else {
+                break;
+            };
+            spans.push(Span {
+                enter: self.hit(ray, &direction, t_enter, scale),
+                exit: self.hit(ray, &direction, t_exit, scale),
+            });
+            t = t_exit + 2.0 * self.epsilon;
+            enter = self.march(ray_origin, &direction, t, false);
+        }
+
+        spans
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::sphere::Sphere;
+
+    #[test]
+    fn sphere_matches_analytic_sphere() {
+        let (center, radius) = (Vec3::new(0.5, 0.0, -5.0), 1.5);
+        let sdf = SdfObject::new(Sdf::Sphere { center, radius }, Material::black());
+        let sphere = Sphere { center, radius, material: Material::black() };
+        // Direcciones sin normalizar, para comprobar que la distancia está en unidades del rayo
+        for direction in [Vec3::new(0.0, 0.0, -1.0), Vec3::new(0.1, 0.2, -2.0), Vec3::new(0.2, -0.1, -1.0)] {
+            let ray = Ray::new(Vec3::zeros(), direction, 0.0);
+            let (marched, exact) = (sdf.ray_intersect(&ray), sphere.ray_intersect(&ray));
+            assert!(marched.is_intersecting && exact.is_intersecting);
+            assert!((marched.distance - exact.distance).abs() < 2e-3, "{} frente a {}", marched.distance, exact.distance);
+            assert!(marched.normal.dot(&exact.normal) > 0.999);
+        }
+    }
+
+    #[test]
+    fn smooth_operators_blend_only_near_the_seam() {
+        let a = Sdf::Sphere { center: Vec3::new(-1.0, 0.0, 0.0), radius: 1.0 };
+        let b = Sdf::Sphere { center: Vec3::new(1.0, 0.0, 0.0), radius: 1.0 };
+        let union = a.clone().smooth_union(b.clone(), 0.5);
+        let subtraction = a.clone().smooth_subtraction(b.clone(), 0.5);
+
+        // Lejos de la unión de las dos esferas es un mínimo (o máximo) normal
+        let far = Vec3::new(-3.0, 0.0, 0.0);
+        assert_eq!(union.distance(&far), a.distance(&far).min(b.distance(&far)));
+        assert_eq!(subtraction.distance(&far), a.distance(&far).max(-b.distance(&far)));
+        // En el punto de contacto el mínimo suave rellena el hueco como mucho `k / 4`
+        let seam = Vec3::new(0.0, 0.5, 0.0);
+        let hard = a.distance(&seam).min(b.distance(&seam));
+        assert!(union.distance(&seam) < hard && union.distance(&seam) >= hard - 0.125);
+    }
+
+    #[test]
+    fn step_and_distance_limits_miss_instead_of_hitting() {
+        // Un rayo rasante sobre la cara superior de una caja necesita muchos pasos
+        let slab = Sdf::Box { center: Vec3::new(0.0, -1.0, 0.0), half_size: Vec3::new(100.0, 1.0, 100.0) };
+        let grazing = Ray::new(Vec3::new(0.0, 1.0, 0.0), Vec3::new(1.0, -0.2, 0.0), 0.0);
+        let hit = SdfObject::new(slab.clone(), Material::black()).ray_intersect(&grazing);
+        assert!(hit.is_intersecting && hit.point.y.abs() < 1e-2);
+        let few_steps = SdfObject::new(slab, Material::black()).with_limits(5, 100.0, 1e-3);
+        assert!(!few_steps.ray_intersect(&grazing).is_intersecting);
+        assert!(few_steps.ray_spans(&grazing).is_empty());
+
+        // Una esfera más allá del alcance no se toca
+        let sphere = Sdf::Sphere { center: Vec3::new(0.0, 0.0, -10.0), radius: 1.0 };
+        let short = SdfObject::new(sphere, Material::black()).with_limits(128, 5.0, 1e-3);
+        let ray = Ray::new(Vec3::zeros(), Vec3::new(0.0, 0.0, -1.0), 0.0);
+        assert!(!short.ray_intersect(&ray).is_intersecting);
+        assert!(short.ray_spans(&ray).is_empty());
+    }
+}
@@ -0,0 +1,204 @@
+
+use nalgebra_glm::Vec3;
+use std::f32::consts::PI;
+use crate::color::{self, Color};
+use crate::math::smoothstep;
+use crate::sampling;
+
+// Distancia a la que se coloca la luz del sol: lo bastante lejos para que sus rayos sean casi paralelos
+const SUN_DISTANCE: f32 = 500.0;
+// Radio angular aparente del disco solar (más grande que el real para que se vea en baja resolución)
+const SUN_ANGULAR_RADIUS: f32 = 0.035;
+// Radiancia del disco solar respecto al blanco de pantalla: mucho más brillante que el cielo,
+// así resplandece en el posprocesado y se refleja con fuerza
+const SUN_RADIANCE: f32 = 40.0;
+// Factor de exposición para pasar de kcd/m² del modelo a colores de pantalla
+const EXPOSURE: f32 = 0.12;
+// Color del cielo cuando el sol está muy por debajo del horizonte
+const NIGHT_COLOR: Color = Color::new(4, 12, 36);
+const MOON_ANGULAR_RADIUS: f32 = 0.03;
+const MOON_COLOR: Color = Color::new(225, 230, 245);
+// Luz que recibe la escena de la luna llena, tenue y azulada
+const MOONLIGHT_COLOR: Color = Color::new(150, 170, 230);
+// Celdas por unidad de la rejilla del campo de estrellas y fracción de celdas con estrella
+const STAR_DENSITY: f32 = 120.0;
+const STAR_THRESHOLD: f32 = 0.996;
+
+// Coeficientes de la función de distribución de Perez
+#[derive(Debug, Clone, Copy)]
+struct Perez([f32; 5]);
+
+impl Perez {
+    fn eval(&self, cos_theta: f32, gamma: f32) -> f32 {
+        let [a, b, c, d, e] = self.0;
+        (1.0 + a * (b / cos_theta.max(0.01)).exp()) * (1.0 + c * (d * gamma).exp() + e * gamma.cos().powi(2))
+    }
+}
+
+// Modelo analítico de cielo diurno de Preetham, Shirley y Smits (1999). La posición del sol
+// sale de la hora local, la latitud y el día del año.
+#[derive(Debug, Clone)]
+pub struct Sky {
+    pub turbidity: f32,   // Bruma de la atmósfera: 2 muy claro, 10 muy cargado
+    pub latitude: f32,    // Grados, positivo hacia el norte
+    pub day_of_year: u32,
+    pub sun_direction: Vec3,
+    pub moon_direction: Vec3,
+    pub moon_phase: f32,  // 0 luna llena, 0.5 luna nueva
+    hours: f32,
+    zenith: [f32; 3],     // Y, x, y en el cénit
+    perez: [Perez; 3],
+}
+
+impl Sky {
+    pub fn new(turbidity: f32, latitude: f32, day_of_year: u32) -> Self {
+        let mut sky = Sky {
+            turbidity,
+            latitude,
+            day_of_year,
+            sun_direction: Vec3::new(0.0, 1.0, 0.0),
+            moon_direction: Vec3::new(0.0, -1.0, 0.0),
+            moon_phase: 0.0,
+            hours: 12.0,
+            zenith: [0.0; 3],
+            perez: [Perez([0.0; 5]); 3],
+        };
+        sky.set_time(12.0);
+        sky
+    }
+
+    // Coloca el sol y la luna para la hora solar local `hours` (0..24) y recalcula los coeficientes
+    pub fn set_time(&mut self, hours: f32) {
+        self.hours = hours;
+        self.sun_direction = sun_direction(hours, self.latitude, self.day_of_year);
+        // La luna llena está frente al sol con la declinación opuesta; cada fase la retrasa
+        self.moon_direction = sun_direction(hours - 12.0 - 24.0 * self.moon_phase, self.latitude, self.day_of_year + 182);
+
+        // El modelo no está definido con el sol bajo el horizonte: se fija en el horizonte
+        // y `twilight` se encarga de oscurecer el resultado
+        let theta_s = self.sun_direction.y.clamp(0.0, 1.0).acos().min(PI / 2.0 - 0.01);
+        let t = self.turbidity;
+        let chi = (4.0 / 9.0 - t / 120.0) * (PI - 2.0 * theta_s);
+        let zenith_luminance = (4.0453 * t - 4.9710) * chi.tan() - 0.2155 * t + 2.4192;
+        let thetas = [theta_s.powi(3), theta_s.powi(2), theta_s, 1.0];
+        let dot = |c: [f32; 4]| c.iter().zip(thetas.iter()).map(|(a, b)| a * b).sum::<f32>();
+        let zenith_x = t * t * dot([0.00166, -0.00375, 0.00209, 0.0]) + t * dot([-0.02903, 0.06377, -0.03202, 0.00394]) + dot([0.11693, -0.21196, 0.06052, 0.25886]);
+        let zenith_y = t * t * dot([0.00275, -0.00610, 0.00317, 0.0]) + t * dot([-0.04214, 0.08970, -0.04153, 0.00516]) + dot([0.15346, -0.26756, 0.06670, 0.26688]);
+        self.zenith = [zenith_luminance, zenith_x, zenith_y];
+
+        self.perez = [
+            Perez([0.1787 * t - 1.4630, -0.3554 * t + 0.4275, -0.0227 * t + 5.3251, 0.1206 * t - 2.5771, -0.0670 * t + 0.3703]),
+            Perez([-0.0193 * t - 0.2592, -0.0665 * t + 0.0008, -0.0004 * t + 0.2125, -0.0641 * t - 0.8989, -0.0033 * t + 0.0452]),
+            Perez([-0.0167 * t - 0.2608, -0.0950 * t + 0.0092, -0.0079 * t + 0.2102, -0.0441 * t - 1.6537, -0.0109 * t + 0.0529]),
+        ];
+    }
+
+    // Atenuación del cielo y del sol en el crepúsculo: 1 de día, 0 de noche cerrada
+    fn twilight(&self) -> f32 {
+        smoothstep(-0.12, 0.05, self.sun_direction.y)
+    }
+
+    // Radiancia lineal del cielo en la dirección `direction`, con el disco del sol; fondo de los
+    // rayos perdidos. El sol pasa de 1, lo demás queda por debajo.
+    pub fn radiance(&self, direction: &Vec3) -> Vec3 {
+        let direction = direction.normalize();
+        // Debajo del horizonte se repite el color del horizonte
+        let cos_theta = direction.y.max(0.0);
+        let theta_s = self.sun_direction.y.clamp(0.0, 1.0).acos().min(PI / 2.0 - 0.01);
+        let flat_sun = Vec3::new(self.sun_direction.x, theta_s.cos(), self.sun_direction.z).normalize();
+        let gamma = direction.dot(&flat_sun).clamp(-1.0, 1.0).acos();
+
+        let [luminance, x, y] = [0, 1, 2].map(|i| self.zenith[i] * self.perez[i].eval(cos_theta, gamma) / self.perez[i].eval(1.0, theta_s));
+        // La exposición del modelo da colores de pantalla; se pasan a radiancia lineal
+        let sky = xyy_to_rgb(x, y, luminance * EXPOSURE).map(|c| color::srgb_decode(1.0 - (-c).exp()));
+
+        let mut color = sky * self.twilight();
+        if direction.dot(&self.sun_direction) > SUN_ANGULAR_RADIUS.cos() {
+            color += self.sun_color().to_linear() * (SUN_RADIANCE * self.twilight());
+        }
+        let darkness = 1.0 - self.twilight();
+        color += NIGHT_COLOR.to_linear() * darkness;
+
+        // Las estrellas aparecen al oscurecer; la luna se ve también de día, más pálida
+        if direction.y > 0.0 {
+            color += Vec3::new(1.0, 1.0, 1.0) * (self.star(&direction) * darkness);
+        }
+        if direction.dot(&self.moon_direction) > MOON_ANGULAR_RADIUS.cos() && self.moon_direction.y > 0.0 {
+            color += MOON_COLOR.to_linear() * (self.moon_illumination() * (0.3 + 0.7 * darkness));
+        }
+        color
+    }
+
+    // Brillo de la estrella en la dirección, o 0. La esfera celeste gira alrededor del polo
+    // norte celeste una vuelta por día, así las estrellas se mueven con el reloj.
+    fn star(&self, direction: &Vec3) -> f32 {
+        let latitude = self.latitude.to_radians();
+        let pole = Vec3::new(0.0, latitude.sin(), -latitude.cos());
+        let celestial = nalgebra_glm::rotate_vec3(direction, -self.hours * 15f32.to_radians(), &pole);
+        let cell = (celestial * STAR_DENSITY).map(f32::floor);
+        let h = sampling::hash(cell.x * 127.1 + cell.y * 311.7 + cell.z * 74.7);
+        if h < STAR_THRESHOLD {
+            return 0.0;
+        }
+        (h - STAR_THRESHOLD) / (1.0 - STAR_THRESHOLD)
+    }
+
+    // Fracción iluminada del disco lunar según la fase
+    fn moon_illumination(&self) -> f32 {
+        0.5 + 0.5 * (2.0 * PI * self.moon_phase).cos()
+    }
+
+    // Posición, color e intensidad de la luz de la luna; se apaga de día y bajo el horizonte
+    pub fn moon_light(&self, max_intensity: f32) -> (Vec3, Color, f32) {
+        let visible = smoothstep(-0.02, 0.1, self.moon_direction.y);
+        let intensity = max_intensity * visible * self.moon_illumination() * (1.0 - self.twilight());
+        (self.moon_direction * SUN_DISTANCE, MOONLIGHT_COLOR, intensity)
+    }
+
+    // Color del sol tras atravesar la atmósfera: extinción de Rayleigh y de aerosoles
+    // según la masa de aire, normalizada para que al cénit sea blanco
+    pub fn sun_color(&self) -> Color {
+        let transmittance = self.sun_transmittance(self.sun_direction.y);
+        let zenith = self.sun_transmittance(1.0);
+        Color::from_linear(&transmittance.component_div(&zenith))
+    }
+
+    fn sun_transmittance(&self, cos_zenith: f32) -> Vec3 {
+        let zenith_degrees = cos_zenith.clamp(-1.0, 1.0).acos().to_degrees().min(93.0);
+        // Masa de aire de Kasten y Young
+        let air_mass = 1.0 / (cos_zenith.max(0.0) + 0.50572 * (96.07995 - zenith_degrees).powf(-1.6364));
+        let beta = 0.04608 * self.turbidity - 0.04586;
+        let extinction = [0.68f32, 0.55, 0.44].map(|lambda| 0.008735 * lambda.powf(-4.08) + beta * lambda.powf(-1.3));
+        Vec3::new(extinction[0], extinction[1], extinction[2]).map(|tau| (-tau * air_mass).exp())
+    }
+
+    // Posición, color e intensidad de la luz del sol; intensidad nula de noche
+    pub fn sun_light(&self, max_intensity: f32) -> (Vec3, Color, f32) {
+        let intensity = max_intensity * smoothstep(-0.02, 0.1, self.sun_direction.y);
+        (self.sun_direction * SUN_DISTANCE, self.sun_color(), intensity)
+    }
+}
+
+// Dirección hacia el sol en coordenadas de la escena (x este, y arriba, -z norte)
+pub fn sun_direction(hours: f32, latitude: f32, day_of_year: u32) -> Vec3 {
+    let declination = (-23.44f32).to_radians() * (2.0 * PI / 365.0 * (day_of_year as f32 + 10.0)).cos();
+    let hour_angle = (hours - 12.0) * 15f32.to_radians();
+    let latitude = latitude.to_radians();
+
+    let east = -declination.cos() * hour_angle.sin();
+    let north = declination.sin() * latitude.cos() - declination.cos() * hour_angle.cos() * latitude.sin();
+    let up = declination.sin() * latitude.sin() + declination.cos() * hour_angle.cos() * latitude.cos();
+    Vec3::new(east, up, -north).normalize()
+}
+
+// Cromaticidad xy y luminancia Y a RGB lineal (primarios sRGB)
+fn xyy_to_rgb(x: f32, y: f32, luminance: f32) -> Vec3 {
+    let big_x = x / y * luminance;
+    let big_z = (1.0 - x - y) / y * luminance;
+    Vec3::new(
+        3.2406 * big_x - 1.5372 * luminance - 0.4986 * big_z,
+        -0.9689 * big_x + 1.8758 * luminance + 0.0415 * big_z,
+        0.0557 * big_x - 0.2040 * luminance + 1.0570 * big_z,
+    )
+    .map(|c| c.max(0.0))
+}
@@ -1,6 +1,7 @@
 
 use nalgebra_glm::{Vec3, dot};
-use crate::ray_intersect::{RayIntersect, Intersect};
+use std::f32::consts::PI;
+use crate::ray_intersect::{Ray, RayIntersect, Intersect, Span};
 use crate::material::Material;
 
 pub struct Sphere {
@@ -9,8 +10,27 @@ pub struct Sphere {
     pub material: Material,
 }
 
+impl Sphere {
+    // Coordenadas UV esféricas a partir de la normal en el punto
+    pub fn get_uv(normal: &Vec3) -> (f32, f32) {
+        let u = 0.5 + normal.z.atan2(normal.x) / (2.0 * PI);
+        let v = 0.5 - normal.y.clamp(-1.0, 1.0).asin() / PI;
+        (u, v)
+    }
+
+    fn surface_hit(&self, point: Vec3, normal: Vec3, distance: f32, ray: &Ray) -> Intersect {
+        let uv_size = 2.0 * PI * self.radius;
+        let cos_theta = normal.dot(&ray.direction.normalize());
+        let local = point - self.center;
+        let footprint = ray.footprint(distance * ray.direction.magnitude());
+        let material = self.material.at_surface(Sphere::get_uv(&normal), &local, uv_size, footprint, cos_theta);
+        Intersect::new(point, normal, distance, material)
+    }
+}
+
 impl RayIntersect for Sphere {
-    fn ray_intersect(&self, ray_origin: &Vec3, ray_direction: &Vec3) -> Intersect {
+    fn ray_intersect(&self, ray: &Ray) -> Intersect {
+        let (ray_origin, ray_direction) = (&ray.origin, &ray.direction);
         let oc = ray_origin - self.center;
 
         let a = dot(ray_direction, ray_direction);
@@ -26,10 +46,35 @@ impl RayIntersect for Sphere {
                 let normal = (point - self.center).normalize();
                 let distance = t;
 
-                return Intersect::new(point, normal, distance, self.material.clone());
+                return self.surface_hit(point, normal, distance, ray);
             }
         }
 
         Intersect::empty()
     }
+
+    fn ray_spans(&self, ray: &Ray) -> Vec<Span> {
+        let (ray_origin, ray_direction) = (&ray.origin, &ray.direction);
+        let oc = ray_origin - self.center;
+
+        let a = dot(ray_direction, ray_direction);
+        let b = 2.0 * dot(&oc, ray_direction);
+        let c = dot(&oc, &oc) - self.radius * self.radius;
+
+        let discriminant = b * b - 4.0 * a * c;
+        if discriminant <= 0.0 {
+            return Vec::new();
+        }
+
+        // Ambas raíces: la menor es la entrada y la mayor la salida
+        let hit = |t: f32| {
+            let point = ray_origin + ray_direction * t;
+            let normal = (point - self.center).normalize();
+            self.surface_hit(point, normal, t, ray)
+        };
+        let t0 = (-b - discriminant.sqrt()) / (2.0 * a);
+        let t1 = (-b + discriminant.sqrt()) / (2.0 * a);
+
+        vec![Span { enter: hit(t0), exit: hit(t1) }]
+    }
 }   
\ No newline at end of file
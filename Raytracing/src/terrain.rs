@@ -0,0 +1,341 @@
+
+use nalgebra_glm::Vec3;
+use crate::material::Material;
+use crate::math::smoothstep;
+use crate::ray_intersect::{Intersect, Ray, RayIntersect, Span};
+
+// Distancia mínima para aceptar una intersección
+const EPSILON: f32 = 1e-4;
+
+// Terreno definido por una malla regular de alturas normalizadas (0.0 a 1.0)
+#[derive(Debug, Clone)]
+pub struct Heightmap {
+    pub min: Vec3,         // Esquina inferior del volumen del terreno
+    pub size: Vec3,        // Extensión en x, altura máxima en y, extensión en z
+    pub columns: usize,    // Vértices a lo largo de x
+    pub rows: usize,       // Vértices a lo largo de z
+    pub heights: Vec<f32>,
+    pub grass: Material,   // Material de las zonas planas y bajas
+    pub stone: Material,   // Material de las pendientes y cumbres
+    pub uv_scale: f32,     // Veces que se repite la textura sobre el terreno
+    normals: Vec<Vec3>,
+    cell_bounds: Vec<(f32, f32)>,  // Altura mínima y máxima de cada celda
+}
+
+impl Heightmap {
+    pub fn new(min: Vec3, size: Vec3, columns: usize, rows: usize, heights: Vec<f32>, grass: Material, stone: Material) -> Self {
+        assert!(columns >= 2 && rows >= 2 && heights.len() == columns * rows);
+        let mut heightmap = Heightmap {
+            min,
+            size,
+            columns,
+            rows,
+            heights,
+            grass,
+            stone,
+            uv_scale: 8.0,
+            normals: Vec::new(),
+            cell_bounds: Vec::new(),
+        };
+        heightmap.rebuild();
+        heightmap
+    }
+
+    // Carga las alturas desde una imagen en escala de grises
+    pub fn load_from_file(path: &str, min: Vec3, size: Vec3, grass: Material, stone: Material) -> Self {
+        let img = image::open(path).expect("Error al abrir el mapa de alturas").to_luma8();
+        let (columns, rows) = (img.width() as usize, img.height() as usize);
+        let heights = img.pixels().map(|p| p.0[0] as f32 / 255.0).collect();
+        Heightmap::new(min, size, columns, rows, heights, grass, stone)
+    }
+
+    // Genera las alturas evaluando una función procedural en coordenadas (0..1, 0..1)
+    pub fn from_fn(resolution: usize, min: Vec3, size: Vec3, grass: Material, stone: Material, height: impl Fn(f32, f32) -> f32) -> Self {
+        let step = 1.0 / (resolution - 1) as f32;
+        let heights = (0..resolution * resolution)
+            .map(|i| height((i % resolution) as f32 * step, (i / resolution) as f32 * step).clamp(0.0, 1.0))
+            .collect();
+        Heightmap::new(min, size, resolution, resolution, heights, grass, stone)
+    }
+
+    fn cell_size(&self) -> (f32, f32) {
+        (self.size.x / (self.columns - 1) as f32, self.size.z / (self.rows - 1) as f32)
+    }
+
+    fn vertex(&self, i: usize, j: usize) -> Vec3 {
+        let (dx, dz) = self.cell_size();
+        Vec3::new(
+            self.min.x + i as f32 * dx,
+            self.min.y + self.heights[j * self.columns + i] * self.size.y,
+            self.min.z + j as f32 * dz,
+        )
+    }
+
+    // Altura del terreno (en coordenadas de mundo) sobre el punto (x, z), si está dentro
+    pub fn height_at(&self, x: f32, z: f32) -> Option<f32> {
+        let (dx, dz) = self.cell_size();
+        let fx = (x - self.min.x) / dx;
+        let fz = (z - self.min.z) / dz;
+        if fx < 0.0 || fz < 0.0 || fx > (self.columns - 1) as f32 || fz > (self.rows - 1) as f32 {
+            return None;
+        }
+        let i = (fx as usize).min(self.columns - 2);
+        let j = (fz as usize).min(self.rows - 2);
+        let (tx, tz) = (fx - i as f32, fz - j as f32);
+        let h = |ci: usize, cj: usize| self.heights[cj * self.columns + ci];
+        // Interpolamos dentro del mismo triángulo que usa `cell_hits`: la diagonal une
+        // las esquinas (i + 1, j) e (i, j + 1)
+        let height = if tx + tz <= 1.0 {
+            h(i, j) + (h(i + 1, j) - h(i, j)) * tx + (h(i, j + 1) - h(i, j)) * tz
+        } else {
+            let d = h(i + 1, j + 1);
+            d + (h(i, j + 1) - d) * (1.0 - tx) + (h(i + 1, j) - d) * (1.0 - tz)
+        };
+        Some(self.min.y + height * self.size.y)
+    }
+
+    // Precalcula normales suaves por vértice y los límites de altura de cada celda
+    fn rebuild(&mut self) {
+        let (dx, dz) = self.cell_size();
+        let h = |i: usize, j: usize| self.heights[j * self.columns + i] * self.size.y;
+
+        self.normals = (0..self.columns * self.rows)
+            .map(|k| {
+                let (i, j) = (k % self.columns, k / self.columns);
+                let (i0, i1) = (i.saturating_sub(1), (i + 1).min(self.columns - 1));
+                let (j0, j1) = (j.saturating_sub(1), (j + 1).min(self.rows - 1));
+                let slope_x = (h(i1, j) - h(i0, j)) / ((i1 - i0) as f32 * dx);
+                let slope_z = (h(i, j1) - h(i, j0)) / ((j1 - j0) as f32 * dz);
+                Vec3::new(-slope_x, 1.0, -slope_z).normalize()
+            })
+            .collect();
+
+        self.cell_bounds = (0..(self.columns - 1) * (self.rows - 1))
+            .map(|k| {
+                let (i, j) = (k % (self.columns - 1), k / (self.columns - 1));
+                let corners = [h(i, j), h(i + 1, j), h(i, j + 1), h(i + 1, j + 1)];
+                let low = corners.iter().cloned().fold(f32::INFINITY, f32::min);
+                let high = corners.iter().cloned().fold(f32::NEG_INFINITY, f32::max);
+                (self.min.y + low, self.min.y + high)
+            })
+            .collect();
+    }
+
+    // Entrada y salida del rayo en la caja que contiene el terreno
+    fn bounds_hit(&self, ray_origin: &Vec3, ray_direction: &Vec3) -> Option<(f32, f32)> {
+        let inv_dir = Vec3::new(1.0, 1.0, 1.0).component_div(ray_direction);
+        let tmin = (self.min - ray_origin).component_mul(&inv_dir);
+        let tmax = (self.min + self.size - ray_origin).component_mul(&inv_dir);
+        let t_near = tmin.x.min(tmax.x).max(tmin.y.min(tmax.y)).max(tmin.z.min(tmax.z));
+        let t_far = tmin.x.max(tmax.x).min(tmin.y.max(tmax.y)).min(tmin.z.max(tmax.z));
+        if t_near < t_far && t_far > 0.0 {
+            Some((t_near, t_far))
+        } else {
+            None
+        }
+    }
+
+    // Recorre las celdas atravesadas por el rayo (DDA en 2D) y devuelve los cruces con la superficie.
+    // Si `first_only` es verdadero se detiene en el primer cruce por delante del origen.
+    fn crossings(&self, ray: &Ray, first_only: bool) -> Vec<Intersect> {
+        let (ray_origin, ray_direction) = (&ray.origin, &ray.direction);
+        let mut hits = Vec::new();
+        let Some((t_near, t_far)) = self.bounds_hit(ray_origin, ray_direction) else {
+            return hits;
+        };
+
+        let (dx, dz) = self.cell_size();
+        let start = ray_origin + ray_direction * t_near.max(0.0);
+        let cells_x = self.columns - 1;
+        let cells_z = self.rows - 1;
+        let mut i = (((start.x - self.min.x) / dx) as isize).clamp(0, cells_x as isize - 1);
+        let mut j = (((start.z - self.min.z) / dz) as isize).clamp(0, cells_z as isize - 1);
+
+        let step_i: isize = if ray_direction.x >= 0.0 { 1 } else { -1 };
+        let step_j: isize = if ray_direction.z >= 0.0 { 1 } else { -1 };
+        let next_boundary = |cell: isize, step: isize, cell_size: f32, origin: f32, min: f32, dir: f32| {
+            if dir == 0.0 {
+                return f32::INFINITY;
+            }
+            let edge = min + (cell + if step > 0 { 1 } else { 0 }) as f32 * cell_size;
+            (edge - origin) / dir
+        };
+        let mut t_next_x = next_boundary(i, step_i, dx, ray_origin.x, self.min.x, ray_direction.x);
+        let mut t_next_z = next_boundary(j, step_j, dz, ray_origin.z, self.min.z, ray_direction.z);
+        let t_delta_x = (dx / ray_direction.x).abs();
+        let t_delta_z = (dz / ray_direction.z).abs();
+
+        let mut t_cell = t_near;
+        loop {
+            let t_exit = t_next_x.min(t_next_z).min(t_far);
+
+            // Solo probamos los triángulos si el rayo pasa por el rango de alturas de la celda
+            let (low, high) = self.cell_bounds[j as usize * cells_x + i as usize];
+            let y_enter = ray_origin.y + ray_direction.y * t_cell;
+            let y_exit = ray_origin.y + ray_direction.y * t_exit;
+            if y_enter.min(y_exit) <= high + EPSILON && y_enter.max(y_exit) >= low - EPSILON {
+                let mut cell_hits = self.cell_hits(i as usize, j as usize, ray);
+                cell_hits.sort_by(|a, b| a.distance.total_cmp(&b.distance));
+                for hit in cell_hits {
+                    if first_only && hit.distance > EPSILON {
+                        return vec![hit];
+                    }
+                    hits.push(hit);
+                }
+            }
+
+            if t_exit >= t_far {
+                break;
+            }
+            if t_next_x < t_next_z {
+                i += step_i;
+                t_cell = t_next_x;
+                t_next_x += t_delta_x;
+            } else {
+                j += step_j;
+                t_cell = t_next_z;
+                t_next_z += t_delta_z;
+            }
+            if i < 0 || j < 0 || i >= cells_x as isize || j >= cells_z as isize {
+                break;
+            }
+        }
+
+        if first_only {
+            hits.retain(|hit| hit.distance > EPSILON);
+        }
+        hits
+    }
+
+    fn cell_hits(&self, i: usize, j: usize, ray: &Ray) -> Vec<Intersect> {
+        let corners = [(i, j), (i + 1, j), (i, j + 1), (i + 1, j + 1)];
+        let [a, b, c, d] = corners.map(|(ci, cj)| (self.vertex(ci, cj), self.normals[cj * self.columns + ci]));
+        [(a, c, b), (b, c, d)]
+            .into_iter()
+            .filter_map(|(p0, p1, p2)| self.triangle_hit(ray, p0, p1, p2))
+            .collect()
+    }
+
+    // Möller–Trumbore con interpolación de las normales de los vértices
+    fn triangle_hit(&self, ray: &Ray, v0: (Vec3, Vec3), v1: (Vec3, Vec3), v2: (Vec3, Vec3)) -> Option<Intersect> {
+        let (ray_origin, ray_direction) = (&ray.origin, &ray.direction);
+        let edge1 = v1.0 - v0.0;
+        let edge2 = v2.0 - v0.0;
+        let pvec = ray_direction.cross(&edge2);
+        let det = edge1.dot(&pvec);
+        if det.abs() < 1e-8 {
+            return None;
+        }
+        let inv_det = 1.0 / det;
+        let tvec = ray_origin - v0.0;
+        let u = tvec.dot(&pvec) * inv_det;
+        if !(0.0..=1.0).contains(&u) {
+            return None;
+        }
+        let qvec = tvec.cross(&edge1);
+        let v = ray_direction.dot(&qvec) * inv_det;
+        if v < 0.0 || u + v > 1.0 {
+            return None;
+        }
+        let t = edge2.dot(&qvec) * inv_det;
+
+        let point = ray_origin + ray_direction * t;
+        let normal = (v0.1 * (1.0 - u - v) + v1.1 * u + v2.1 * v).normalize();
+        let cos_theta = normal.dot(&ray_direction.normalize());
+        let footprint = ray.footprint(t * ray_direction.magnitude());
+        Some(Intersect::new(point, normal, t, self.surface_material(&point, &normal, footprint, cos_theta)))
+    }
+
+    // Mezcla pasto y piedra según la pendiente y la altura del punto
+    fn surface_material(&self, point: &Vec3, normal: &Vec3, footprint: f32, cos_theta: f32) -> Material {
+        let slope = 1.0 - normal.y;
+        let height = (point.y - self.min.y) / self.size.y;
+        let stone_weight = smoothstep(0.15, 0.35, slope).max(smoothstep(0.75, 0.95, height));
+
+        let u = (point.x - self.min.x) / self.size.x * self.uv_scale;
+        let v = (point.z - self.min.z) / self.size.z * self.uv_scale;
+        let local = point - self.min;
+        let uv_size = self.size.x / self.uv_scale;
+        // Sin mezcla solo hace falta muestrear uno de los dos materiales
+        if stone_weight <= 0.0 {
+            return self.grass.at_surface((u, v), &local, uv_size, footprint, cos_theta);
+        }
+        if stone_weight >= 1.0 {
+            return self.stone.at_surface((u, v), &local, uv_size, footprint, cos_theta);
+        }
+        let grass = self.grass.at_surface((u, v), &local, uv_size, footprint, cos_theta);
+        let stone = self.stone.at_surface((u, v), &local, uv_size, footprint, cos_theta);
+
+        let diffuse = grass.diffuse * (1.0 - stone_weight) + stone.diffuse * stone_weight;
+        let mut material = if stone_weight < 0.5 { grass } else { stone };
+        material.diffuse = diffuse;
+        material
+    }
+}
+
+impl RayIntersect for Heightmap {
+    fn ray_intersect(&self, ray: &Ray) -> Intersect {
+        self.crossings(ray, true)
+            .into_iter()
+            .next()
+            .unwrap_or_else(Intersect::empty)
+    }
+
+    // El sólido es el volumen entre la base de la caja y la superficie
+    fn ray_spans(&self, ray: &Ray) -> Vec<Span> {
+        let (ray_origin, ray_direction) = (&ray.origin, &ray.direction);
+        let Some((t_near, t_far)) = self.bounds_hit(ray_origin, ray_direction) else {
+            return Vec::new();
+        };
+        let box_hit = |t: f32, normal: Vec3| {
+            let point = ray_origin + ray_direction * t;
+            Intersect::new(point, normal, t, self.stone.clone())
+        };
+
+        let mut spans = Vec::new();
+        let mut enter: Option<Intersect> = None;
+
+        // Si el rayo entra a la caja por debajo de la superficie, ya está dentro del sólido
+        let entry_point = ray_origin + ray_direction * t_near;
+        if self.height_at(entry_point.x, entry_point.z).is_some_and(|h| entry_point.y < h) {
+            enter = Some(box_hit(t_near, -ray_direction.normalize()));
+        }
+
+        for hit in self.crossings(ray, false) {
+            let entering = hit.normal.dot(ray_direction) < 0.0;
+            match (entering, enter.take()) {
+                (true, None) => enter = Some(hit),
+                (false, Some(start)) => spans.push(Span { enter: start, exit: hit }),
+                (_, start) => enter = start,
+            }
+        }
+        if let Some(start) = enter {
+            spans.push(Span { enter: start, exit: box_hit(t_far, ray_direction.normalize()) });
+        }
+
+        spans
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn vertical_rays_land_on_height_at() {
+        // Alturas con celdas en silla de montar, donde la interpolación bilineal y los
+        // triángulos de la malla no coinciden
+        let terrain = Heightmap::from_fn(9, Vec3::new(-4.0, 0.0, -4.0), Vec3::new(8.0, 2.0, 8.0), Material::black(), Material::black(), |u, v| {
+            0.5 + 0.4 * (7.0 * u).sin() * (5.0 * v).cos()
+        });
+        for i in 0..13 {
+            for j in 0..13 {
+                let (x, z) = (-3.9 + i as f32 * 0.61, -3.9 + j as f32 * 0.59);
+                let hit = terrain.ray_intersect(&Ray::new(Vec3::new(x, 10.0, z), Vec3::new(0.0, -1.0, 0.0), 0.0));
+                let height = terrain.height_at(x, z).expect("El punto está dentro del terreno");
+                assert!(hit.is_intersecting, "El rayo en ({}, {}) no toca el terreno", x, z);
+                assert!((hit.point.y - height).abs() < 1e-4, "En ({}, {}) el rayo toca en {} y height_at da {}", x, z, hit.point.y, height);
+            }
+        }
+    }
+}
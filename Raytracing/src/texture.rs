@@ -1,27 +1,276 @@
 use image::GenericImageView;
 use crate::color::Color;
 
+// Filtro usado al muestrear la textura
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Filter {
+    Nearest,    // Vecino más cercano del nivel 0: el aspecto pixelado de Minecraft, sin mipmaps, así que se ve ruido a lo lejos
+    Bilinear,   // Bilineal en el nivel de mipmap más cercano al detalle pedido
+    Trilinear,  // Bilineal entre dos niveles de mipmap
+}
+
+// Qué hacer con las coordenadas UV fuera de 0..1
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum WrapMode {
+    Repeat,
+    Clamp,
+    Mirror,
+}
+
+// Nivel de detalle a partir de la huella del píxel: `footprint` es el ancho del cono del rayo
+// en la intersección (`Ray::footprint`), que se estira según el coseno entre el rayo y la
+// normal, y `texels_per_unit` los texels por unidad de mundo en la superficie
+pub fn lod(footprint: f32, cos_theta: f32, texels_per_unit: f32) -> f32 {
+    let footprint = footprint / cos_theta.abs().max(0.1);
+    (footprint * texels_per_unit).max(1.0).log2()
+}
+
+#[derive(Debug, Clone)]
+struct MipLevel {
+    data: Vec<u8>,
+    width: u32,
+    height: u32,
+}
+
 #[derive(Debug, Clone)]  // Añadimos Debug y Clone
 pub struct Texture {
     pub data: Vec<u8>,
     pub width: u32,
     pub height: u32,
+    pub filter: Filter,
+    pub wrap: WrapMode,
+    mips: Vec<MipLevel>,  // Niveles 1.. de la cadena de mipmaps (el 0 es `data`)
 }
 
 
 impl Texture {
+    pub fn new(data: Vec<u8>, width: u32, height: u32) -> Self {
+        let mut texture = Texture::without_mips(data, width, height);
+        texture.build_mipmaps();
+        texture
+    }
+
+    // Textura sin cadena de mipmaps, para quien la rellena después y llama a `build_mipmaps`
+    pub fn without_mips(data: Vec<u8>, width: u32, height: u32) -> Self {
+        Texture { data, width, height, filter: Filter::Nearest, wrap: WrapMode::Repeat, mips: Vec::new() }
+    }
+
     pub fn load_from_file(path: &str) -> Self {
         let img = image::open(path).expect("Error al abrir la imagen");
         let (width, height) = img.dimensions();
         let data = img.to_rgba8().into_raw();
-        Texture { data, width, height }
+        Texture::new(data, width, height)
+    }
+
+    pub fn with_sampling(mut self, filter: Filter, wrap: WrapMode) -> Self {
+        self.filter = filter;
+        self.wrap = wrap;
+        self
+    }
+
+    // Regenera la cadena de mipmaps promediando bloques de 2x2 texels
+    pub fn build_mipmaps(&mut self) {
+        self.mips.clear();
+        let (mut data, mut width, mut height) = (self.data.clone(), self.width, self.height);
+        while width > 1 || height > 1 {
+            let (next_width, next_height) = ((width / 2).max(1), (height / 2).max(1));
+            let mut next = vec![0u8; (next_width * next_height * 4) as usize];
+            for y in 0..next_height {
+                for x in 0..next_width {
+                    for c in 0..4 {
+                        let mut sum = 0u32;
+                        for (dx, dy) in [(0, 0), (1, 0), (0, 1), (1, 1)] {
+                            let sx = (x * 2 + dx).min(width - 1);
+                            let sy = (y * 2 + dy).min(height - 1);
+                            sum += data[((sy * width + sx) * 4 + c) as usize] as u32;
+                        }
+                        next[((y * next_width + x) * 4 + c) as usize] = (sum / 4) as u8;
+                    }
+                }
+            }
+            self.mips.push(MipLevel { data: next.clone(), width: next_width, height: next_height });
+            data = next;
+            width = next_width;
+            height = next_height;
+        }
+    }
+
+    fn level(&self, level: usize) -> (&[u8], u32, u32) {
+        if level == 0 {
+            (&self.data, self.width, self.height)
+        } else {
+            let mip = &self.mips[(level - 1).min(self.mips.len() - 1)];
+            (&mip.data, mip.width, mip.height)
+        }
+    }
+
+    // Aplica el modo de repetición a un índice de texel dentro de un rango de `size` texels
+    fn wrap_index(&self, i: i64, size: u32) -> u32 {
+        let size = size as i64;
+        match self.wrap {
+            WrapMode::Repeat => i.rem_euclid(size) as u32,
+            WrapMode::Clamp => i.clamp(0, size - 1) as u32,
+            WrapMode::Mirror => {
+                let i = i.rem_euclid(2 * size);
+                (if i < size { i } else { 2 * size - 1 - i }) as u32
+            }
+        }
+    }
+
+    // Muestrea la región `rect` (x, y, ancho, alto en texels del nivel 0) como si fuera
+    // una textura propia, de modo que el modo de repetición se aplica dentro de ella
+    pub fn sample_region(&self, rect: (u32, u32, u32, u32), u: f32, v: f32, lod: f32) -> [f32; 4] {
+        match self.filter {
+            Filter::Nearest => self.fetch_nearest(rect, u, v, 0),
+            Filter::Bilinear => self.fetch_bilinear(rect, u, v, lod.clamp(0.0, self.mips.len() as f32).round() as usize),
+            Filter::Trilinear => {
+                let lod = lod.clamp(0.0, self.mips.len() as f32);
+                let level = lod.floor() as usize;
+                let t = lod - level as f32;
+                let a = self.fetch_bilinear(rect, u, v, level);
+                if t == 0.0 {
+                    return a;
+                }
+                let b = self.fetch_bilinear(rect, u, v, level + 1);
+                [0, 1, 2, 3].map(|c| a[c] * (1.0 - t) + b[c] * t)
+            }
+        }
+    }
+
+    fn texel(&self, rect: (u32, u32, u32, u32), x: i64, y: i64, level: usize) -> [f32; 4] {
+        let (data, width, _) = self.level(level);
+        let (x0, y0, w, h) = (rect.0 >> level, rect.1 >> level, (rect.2 >> level).max(1), (rect.3 >> level).max(1));
+        let tx = x0 + self.wrap_index(x, w);
+        let ty = y0 + self.wrap_index(y, h);
+        let idx = ((ty * width + tx) * 4) as usize;
+        [data[idx] as f32, data[idx + 1] as f32, data[idx + 2] as f32, data[idx + 3] as f32]
+    }
+
+    fn fetch_nearest(&self, rect: (u32, u32, u32, u32), u: f32, v: f32, level: usize) -> [f32; 4] {
+        let (w, h) = ((rect.2 >> level).max(1) as f32, (rect.3 >> level).max(1) as f32);
+        self.texel(rect, (u * w).floor() as i64, (v * h).floor() as i64, level)
+    }
+
+    fn fetch_bilinear(&self, rect: (u32, u32, u32, u32), u: f32, v: f32, level: usize) -> [f32; 4] {
+        let (w, h) = ((rect.2 >> level).max(1) as f32, (rect.3 >> level).max(1) as f32);
+        let x = u * w - 0.5;
+        let y = v * h - 0.5;
+        let (xi, yi) = (x.floor() as i64, y.floor() as i64);
+        let (fx, fy) = (x - x.floor(), y - y.floor());
+        let t00 = self.texel(rect, xi, yi, level);
+        let t10 = self.texel(rect, xi + 1, yi, level);
+        let t01 = self.texel(rect, xi, yi + 1, level);
+        let t11 = self.texel(rect, xi + 1, yi + 1, level);
+        [0, 1, 2, 3].map(|c| {
+            let top = t00[c] * (1.0 - fx) + t10[c] * fx;
+            let bottom = t01[c] * (1.0 - fx) + t11[c] * fx;
+            top * (1.0 - fy) + bottom * fy
+        })
+    }
+
+    // Color filtrado con el nivel de detalle `lod` (0 = resolución completa)
+    pub fn sample(&self, u: f32, v: f32, lod: f32) -> Color {
+        let [r, g, b, _] = self.sample_region((0, 0, self.width, self.height), u, v, lod);
+        Color::new(r as u8, g as u8, b as u8)
     }
 
     // Obtener color a partir de las coordenadas UV
     pub fn get_color(&self, u: f32, v: f32) -> Color {
-        let u = (u * self.width as f32) as usize % self.width as usize;
-        let v = (v * self.height as f32) as usize % self.height as usize;
-        let idx = (v * self.width as usize + u) * 4;
-        Color::new(self.data[idx], self.data[idx + 1], self.data[idx + 2])
+        self.sample(u, v, 0.0)
+    }
+
+    // Opacidad (0.0 a 1.0) del canal alfa en las coordenadas UV (siempre del nivel 0)
+    pub fn get_alpha(&self, u: f32, v: f32) -> f32 {
+        self.fetch_nearest((0, 0, self.width, self.height), u, v, 0)[3] / 255.0
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // Textura con el canal rojo dado fila a fila; el resto en negro y opaco
+    fn texture(width: u32, height: u32, red: &[u8], filter: Filter, wrap: WrapMode) -> Texture {
+        let data = red.iter().flat_map(|&r| [r, 0, 0, 255]).collect();
+        Texture::new(data, width, height).with_sampling(filter, wrap)
+    }
+
+    fn red(texture: &Texture, u: f32, v: f32, lod: f32) -> f32 {
+        texture.sample_region((0, 0, texture.width, texture.height), u, v, lod)[0]
+    }
+
+    #[test]
+    fn wrap_modes_outside_unit_range() {
+        let row = [0, 10, 20, 30];
+        // Texels -2, -1, 4 y 5 de una fila de cuatro
+        let us = [-0.375, -0.125, 1.125, 1.375];
+        let expected = [
+            (WrapMode::Repeat, [20.0, 30.0, 0.0, 10.0]),
+            (WrapMode::Clamp, [0.0, 0.0, 30.0, 30.0]),
+            (WrapMode::Mirror, [10.0, 0.0, 30.0, 20.0]),
+        ];
+        for (wrap, values) in expected {
+            let texture = texture(4, 1, &row, Filter::Nearest, wrap);
+            for (u, value) in us.iter().zip(values) {
+                assert_eq!(red(&texture, *u, 0.5, 0.0), value, "{:?} en u = {}", wrap, u);
+            }
+        }
+    }
+
+    #[test]
+    fn nearest_and_bilinear_at_centres_and_edges() {
+        let row = [0, 10, 20, 30];
+        let nearest = texture(4, 1, &row, Filter::Nearest, WrapMode::Repeat);
+        let bilinear = texture(4, 1, &row, Filter::Bilinear, WrapMode::Repeat);
+        let clamped = texture(4, 1, &row, Filter::Bilinear, WrapMode::Clamp);
+
+        // En el centro de un texel los dos filtros dan su valor
+        assert_eq!(red(&nearest, 0.375, 0.5, 0.0), 10.0);
+        assert_eq!(red(&bilinear, 0.375, 0.5, 0.0), 10.0);
+        // En el borde entre dos texels el vecino más cercano elige uno y el bilineal los promedia
+        assert_eq!(red(&nearest, 0.5, 0.5, 0.0), 20.0);
+        assert_eq!(red(&bilinear, 0.5, 0.5, 0.0), 15.0);
+        // En el borde de la textura el bilineal mezcla con el otro extremo solo si se repite
+        assert_eq!(red(&bilinear, 0.0, 0.5, 0.0), 15.0);
+        assert_eq!(red(&clamped, 0.0, 0.5, 0.0), 0.0);
+    }
+
+    #[test]
+    fn mip_levels_average_texels() {
+        let grid = [
+            0, 4, 8, 12,
+            16, 20, 24, 28,
+            32, 36, 40, 44,
+            48, 52, 56, 60,
+        ];
+        let bilinear = texture(4, 4, &grid, Filter::Bilinear, WrapMode::Clamp);
+        let trilinear = texture(4, 4, &grid, Filter::Trilinear, WrapMode::Clamp);
+
+        // Nivel 1: cada texel promedia un bloque de 2x2; nivel 2: toda la textura
+        assert_eq!(red(&bilinear, 0.25, 0.25, 1.0), 10.0);
+        assert_eq!(red(&bilinear, 0.75, 0.75, 1.0), 50.0);
+        assert_eq!(red(&bilinear, 0.5, 0.5, 2.0), 30.0);
+        // El bilineal redondea el nivel; el trilineal mezcla los dos más cercanos
+        assert_eq!(red(&bilinear, 0.125, 0.125, 0.6), 10.0);
+        assert_eq!(red(&trilinear, 0.125, 0.125, 0.5), 5.0);
+    }
+
+    #[test]
+    fn sample_region_stays_inside_its_tile() {
+        let tiles = [
+            100, 100, 200, 200,
+            100, 100, 200, 200,
+        ];
+        for filter in [Filter::Nearest, Filter::Bilinear, Filter::Trilinear] {
+            for wrap in [WrapMode::Repeat, WrapMode::Clamp, WrapMode::Mirror] {
+                let texture = texture(4, 2, &tiles, filter, wrap);
+                for (u, v) in [(0.0, 0.0), (1.0, 1.0), (-0.3, 0.5), (1.7, -2.2)] {
+                    for lod in [0.0, 0.5, 1.0] {
+                        assert_eq!(texture.sample_region((0, 0, 2, 2), u, v, lod)[0], 100.0, "{:?} {:?} ({}, {})", filter, wrap, u, v);
+                        assert_eq!(texture.sample_region((2, 0, 2, 2), u, v, lod)[0], 200.0, "{:?} {:?} ({}, {})", filter, wrap, u, v);
+                    }
+                }
+            }
+        }
     }
 }
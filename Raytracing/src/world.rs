@@ -0,0 +1,533 @@
+
+use nalgebra_glm::Vec3;
+use crate::color::Color;
+use crate::material::Material;
+use crate::noise::Perlin;
+use crate::ray_intersect::{Intersect, Ray, RayIntersect, Span};
+use crate::block::{BlockId, BlockRegistry, TextureAtlas};
+use crate::cube::CubeFace;
+use crate::texture::{self, Texture};
+use std::rc::Rc;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Block {
+    Air,
+    Grass,
+    Dirt,
+    Stone,
+    Sand,
+    Snow,
+    Water,
+    Wood,
+    Leaves,
+    CoalOre,
+    IronOre,
+}
+
+impl Block {
+    pub fn is_solid(self) -> bool {
+        self != Block::Air
+    }
+
+    pub fn id(self) -> BlockId {
+        self as BlockId
+    }
+}
+
+// Registro con los bloques del mundo, en el mismo orden que el enum `Block`
+pub fn block_registry(grass: &Texture, stone: &Texture, wood: &Texture) -> BlockRegistry {
+    let mut atlas = TextureAtlas::new(32, 4, 4);
+    let grass_top = atlas.add_texture(grass);
+    let stone_tile = atlas.add_texture(stone);
+    let wood_tile = atlas.add_texture(wood);
+    let dirt = atlas.add_color(Color::new(120, 85, 55));
+    let tile_size = atlas.tile_size;
+    // Costado del pasto: franja de pasto arriba y tierra debajo
+    let grass_side = atlas.add_fn(|x, y| {
+        if y < tile_size / 4 {
+            let rgb = grass.get_color(x as f32 / tile_size as f32, y as f32 / tile_size as f32).to_hex();
+            [(rgb >> 16) as u8, (rgb >> 8) as u8, rgb as u8, 255]
+        } else {
+            [120, 85, 55, 255]
+        }
+    });
+    let sand = atlas.add_color(Color::new(220, 205, 150));
+    let snow = atlas.add_color(Color::new(240, 245, 250));
+    let water = atlas.add_color(Color::new(40, 80, 170));
+    let wood_top = atlas.add_color(Color::new(160, 120, 70));
+    // Hojas con huecos transparentes según un ruido, para el recorte por alfa
+    let leaf_noise = Perlin::new(11);
+    let leaves = atlas.add_fn(|x, y| {
+        let n = leaf_noise.noise2(x as f32 * 0.35, y as f32 * 0.35);
+        let shade = (110.0 + 40.0 * n) as u8;
+        [40, shade, 40, if n < -0.15 { 0 } else { 255 }]
+    });
+    let coal = atlas.add_color(Color::new(50, 50, 50));
+    let iron = atlas.add_color(Color::new(190, 150, 120));
+    atlas.finish();
+
+    let mut registry = BlockRegistry::new(atlas);
+    let matte = registry.add_material(Material::new(Color::new(0, 0, 0), 1.0, [0.4, 0.1], 0.0, 0.0));
+    let rock = registry.add_material(Material::new(Color::new(0, 0, 0), 5.0, [0.4, 0.2], 0.1, 0.0));
+    let shiny = registry.add_material(Material::new(Color::new(0, 0, 0), 30.0, [0.4, 0.4], 0.3, 0.0));
+    let liquid = registry.add_material(Material::dielectric(Color::new(40, 110, 160), 1.5, 1.33));
+
+    let foliage = registry.add_material(Material::new(Color::new(0, 0, 0), 1.0, [0.4, 0.1], 0.0, 0.0).with_alpha_cutout(0.5));
+
+    let air = registry.add_material(Material::black());
+    let blocks = [
+        (Block::Air, "air", air, dirt, dirt, dirt),
+        (Block::Grass, "grass", matte, grass_top, grass_side, dirt),
+        (Block::Dirt, "dirt", matte, dirt, dirt, dirt),
+        (Block::Stone, "stone", rock, stone_tile, stone_tile, stone_tile),
+        (Block::Sand, "sand", matte, sand, sand, sand),
+        (Block::Snow, "snow", rock, snow, snow, snow),
+        (Block::Water, "water", liquid, water, water, water),
+        (Block::Wood, "wood", matte, wood_top, wood_tile, wood_top),
+        (Block::Leaves, "leaves", foliage, leaves, leaves, leaves),
+        (Block::CoalOre, "coal_ore", rock, coal, stone_tile, coal),
+        (Block::IronOre, "iron_ore", shiny, iron, iron, iron),
+    ];
+    for (block, name, material, top, side, bottom) in blocks {
+        // `Block::id` es la posición en el enum, así que el registro tiene que seguir ese orden
+        let id = registry.register(name, material, top, side, bottom);
+        assert_eq!(id, block.id(), "El bloque {} no se registró en el orden del enum", name);
+    }
+    registry
+}
+
+// Rejilla regular de bloques cúbicos alineados a los ejes
+#[derive(Debug, Clone)]
+pub struct BlockGrid {
+    pub origin: Vec3,      // Esquina inferior del bloque (0, 0, 0)
+    pub block_size: f32,
+    pub size_x: usize,
+    pub size_y: usize,
+    pub size_z: usize,
+    blocks: Vec<Block>,
+    registry: Rc<BlockRegistry>,  // Texturas y materiales de cada tipo, indexados por `Block::id`
+}
+
+impl BlockGrid {
+    pub fn new(origin: Vec3, block_size: f32, size_x: usize, size_y: usize, size_z: usize, registry: &Rc<BlockRegistry>) -> Self {
+        BlockGrid {
+            origin,
+            block_size,
+            size_x,
+            size_y,
+            size_z,
+            blocks: vec![Block::Air; size_x * size_y * size_z],
+            registry: Rc::clone(registry),
+        }
+    }
+
+    fn index(&self, x: usize, y: usize, z: usize) -> usize {
+        (y * self.size_z + z) * self.size_x + x
+    }
+
+    pub fn get(&self, x: i32, y: i32, z: i32) -> Block {
+        if x < 0 || y < 0 || z < 0 || x as usize >= self.size_x || y as usize >= self.size_y || z as usize >= self.size_z {
+            return Block::Air;
+        }
+        self.blocks[self.index(x as usize, y as usize, z as usize)]
+    }
+
+    pub fn set(&mut self, x: i32, y: i32, z: i32, block: Block) {
+        if x < 0 || y < 0 || z < 0 || x as usize >= self.size_x || y as usize >= self.size_y || z as usize >= self.size_z {
+            return;
+        }
+        let index = self.index(x as usize, y as usize, z as usize);
+        self.blocks[index] = block;
+    }
+
+    fn max(&self) -> Vec3 {
+        self.origin + Vec3::new(self.size_x as f32, self.size_y as f32, self.size_z as f32) * self.block_size
+    }
+
+    // Recorre las celdas que atraviesa el rayo (Amanatides-Woo). Para cada celda llama a `visit`
+    // con la distancia de entrada, la normal de la cara por la que entra y el bloque; se detiene
+    // cuando `visit` devuelve `false`. Devuelve la distancia a la que el rayo sale de la rejilla.
+    fn traverse(&self, ray_origin: &Vec3, ray_direction: &Vec3, mut visit: impl FnMut(f32, Vec3, Block) -> bool) -> f32 {
+        let inv_dir = Vec3::new(1.0, 1.0, 1.0).component_div(ray_direction);
+        let tmin = (self.origin - ray_origin).component_mul(&inv_dir);
+        let tmax = (self.max() - ray_origin).component_mul(&inv_dir);
+        let t1 = Vec3::new(tmin.x.min(tmax.x), tmin.y.min(tmax.y), tmin.z.min(tmax.z));
+        let t2 = Vec3::new(tmin.x.max(tmax.x), tmin.y.max(tmax.y), tmin.z.max(tmax.z));
+        let t_near = t1.x.max(t1.y).max(t1.z);
+        let t_far = t2.x.min(t2.y).min(t2.z);
+        if t_near >= t_far || t_far <= 0.0 {
+            return t_far;
+        }
+
+        // Normal de la cara de entrada a la rejilla
+        let entry_axis = if t_near == t1.x { 0 } else if t_near == t1.y { 1 } else { 2 };
+        let mut normal = Vec3::zeros();
+        normal[entry_axis] = -ray_direction[entry_axis].signum();
+
+        let start = ray_origin + ray_direction * t_near;
+        let local = (start - self.origin) / self.block_size;
+        let sizes = [self.size_x as i32, self.size_y as i32, self.size_z as i32];
+        let mut cell = [0i32; 3];
+        let mut step = [0i32; 3];
+        let mut t_next = [f32::INFINITY; 3];
+        let mut t_delta = [f32::INFINITY; 3];
+        for axis in 0..3 {
+            cell[axis] = (local[axis].floor() as i32).clamp(0, sizes[axis] - 1);
+            if ray_direction[axis] > 0.0 {
+                step[axis] = 1;
+                let edge = self.origin[axis] + (cell[axis] + 1) as f32 * self.block_size;
+                t_next[axis] = (edge - ray_origin[axis]) * inv_dir[axis];
+                t_delta[axis] = self.block_size * inv_dir[axis];
+            } else if ray_direction[axis] < 0.0 {
+                step[axis] = -1;
+                let edge = self.origin[axis] + cell[axis] as f32 * self.block_size;
+                t_next[axis] = (edge - ray_origin[axis]) * inv_dir[axis];
+                t_delta[axis] = -self.block_size * inv_dir[axis];
+            }
+        }
+
+        let mut t = t_near;
+        loop {
+            if !visit(t, normal, self.get(cell[0], cell[1], cell[2])) {
+                return t_far;
+            }
+
+            let axis = if t_next[0] < t_next[1] {
+                if t_next[0] < t_next[2] { 0 } else { 2 }
+            } else if t_next[1] < t_next[2] {
+                1
+            } else {
+                2
+            };
+            if t_next[axis] >= t_far {
+                return t_far;
+            }
+            t = t_next[axis];
+            t_next[axis] += t_delta[axis];
+            cell[axis] += step[axis];
+            normal = Vec3::zeros();
+            normal[axis] = -step[axis] as f32;
+            if cell[axis] < 0 || cell[axis] >= sizes[axis] {
+                return t_far;
+            }
+        }
+    }
+
+    // Cara y coordenadas UV del punto en el bloque según la normal de la cara
+    fn face_uv(&self, point: &Vec3, normal: &Vec3) -> (CubeFace, (f32, f32)) {
+        let local = ((point - self.origin) / self.block_size).map(|c| c - c.floor());
+        let local = Vec3::new(
+            if normal.x > 0.0 { 1.0 } else if normal.x < 0.0 { 0.0 } else { local.x },
+            if normal.y > 0.0 { 1.0 } else if normal.y < 0.0 { 0.0 } else { local.y },
+            if normal.z > 0.0 { 1.0 } else if normal.z < 0.0 { 0.0 } else { local.z },
+        );
+        let face = CubeFace::from_normal(normal);
+        (face, face.uv(&local))
+    }
+
+    fn hit(&self, ray: &Ray, t: f32, normal: Vec3, block: Block) -> Intersect {
+        let point = ray.origin + ray.direction * t;
+        let (face, uv) = self.face_uv(&point, &normal);
+        let texels_per_unit = self.registry.atlas.tile_size as f32 / self.block_size;
+        let cos_theta = normal.dot(&ray.direction.normalize());
+        let lod = texture::lod(ray.footprint(t * ray.direction.magnitude()), cos_theta, texels_per_unit);
+        let material = self.registry.surface_material(block.id(), face, uv, lod);
+        Intersect::new(point, normal, t, material)
+    }
+
+    // Oclusión por vértice al estilo de Minecraft en el punto de una cara: cada esquina se
+    // oscurece según cuántos de sus tres vecinos (dos lados y la diagonal) en la capa de aire
+    // frente a la cara son sólidos, y el resultado se interpola a lo largo de la cara.
+    // 1 es una esquina libre, 0 una esquina encajonada entre dos bloques.
+    pub fn vertex_occlusion(&self, point: &Vec3, normal: &Vec3) -> f32 {
+        let axis = (0..3).max_by(|&a, &b| normal[a].abs().total_cmp(&normal[b].abs())).unwrap_or(1);
+        let (a, b) = ((axis + 1) % 3, (axis + 2) % 3);
+        let local = (point - self.origin) / self.block_size;
+        let mut layer = [0i32; 3];
+        for i in 0..3 {
+            layer[i] = if i == axis { (local[i] + normal[i].signum() * 0.5).floor() as i32 } else { local[i].floor() as i32 };
+        }
+        let (fa, fb) = (local[a] - local[a].floor(), local[b] - local[b].floor());
+
+        let solid = |da: i32, db: i32| {
+            let mut cell = layer;
+            cell[a] += da;
+            cell[b] += db;
+            u8::from(self.get(cell[0], cell[1], cell[2]).is_solid())
+        };
+        let corner = |sa: i32, sb: i32| {
+            let (side1, side2, diagonal) = (solid(sa, 0), solid(0, sb), solid(sa, sb));
+            if side1 == 1 && side2 == 1 { 0.0 } else { (3 - side1 - side2 - diagonal) as f32 / 3.0 }
+        };
+        let low = corner(-1, -1) * (1.0 - fb) + corner(-1, 1) * fb;
+        let high = corner(1, -1) * (1.0 - fb) + corner(1, 1) * fb;
+        low * (1.0 - fa) + high * fa
+    }
+
+    // Indica si el rayo atraviesa la cara del bloque por un texel recortado por alfa
+    fn is_cut(&self, point: &Vec3, normal: &Vec3, block: Block) -> bool {
+        if !self.registry.has_cutout(block.id()) {
+            return false;
+        }
+        let (face, uv) = self.face_uv(point, normal);
+        self.registry.is_cut(block.id(), face, uv)
+    }
+}
+
+impl RayIntersect for BlockGrid {
+    fn ray_intersect(&self, ray: &Ray) -> Intersect {
+        let (ray_origin, ray_direction) = (&ray.origin, &ray.direction);
+        let mut result = Intersect::empty();
+        let mut previous = Block::Air;
+        self.traverse(ray_origin, ray_direction, |t, normal, block| {
+            // Dentro del agua no hay caras entre dos bloques del mismo medio
+            let same_medium = block == previous && self.registry.is_dielectric(block.id());
+            previous = block;
+            if block.is_solid() && t > 0.0 && !same_medium && !self.is_cut(&(ray_origin + ray_direction * t), &normal, block) {
+                result = self.hit(ray, t, normal, block);
+                return false;
+            }
+            true
+        });
+        result
+    }
+
+    fn ray_spans(&self, ray: &Ray) -> Vec<Span> {
+        let (ray_origin, ray_direction) = (&ray.origin, &ray.direction);
+        let mut spans = Vec::new();
+        let mut enter: Option<Intersect> = None;
+        let t_far = self.traverse(ray_origin, ray_direction, |t, normal, block| {
+            match (block.is_solid(), enter.is_some()) {
+                (true, false) => enter = Some(self.hit(ray, t, normal, block)),
+                (false, true) => {
+                    let exit = self.hit(ray, t, -normal, block);
+                    spans.push(Span { enter: enter.take().unwrap(), exit });
+                }
+                _ => {}
+            }
+            true
+        });
+        if let Some(enter) = enter {
+            let exit_point = ray_origin + ray_direction * t_far;
+            let exit = Intersect::new(exit_point, ray_direction.normalize(), t_far, enter.material.clone());
+            spans.push(Span { enter, exit });
+        }
+        spans
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Biome {
+    Plains,
+    Forest,
+    Desert,
+    Mountains,
+}
+
+// Generador de mundos: la misma semilla produce siempre el mismo mundo
+pub struct WorldGenerator {
+    pub seed: u64,
+    pub sea_level: i32,
+    height_noise: Perlin,
+    temperature_noise: Perlin,
+    moisture_noise: Perlin,
+    cave_noise: Perlin,
+    ore_noise: Perlin,
+}
+
+impl WorldGenerator {
+    pub fn new(seed: u64) -> Self {
+        WorldGenerator {
+            seed,
+            sea_level: 6,
+            height_noise: Perlin::new(seed),
+            temperature_noise: Perlin::new(seed.wrapping_add(1)),
+            moisture_noise: Perlin::new(seed.wrapping_add(2)),
+            cave_noise: Perlin::new(seed.wrapping_add(3)),
+            ore_noise: Perlin::new(seed.wrapping_add(4)),
+        }
+    }
+
+    pub fn biome_at(&self, x: i32, z: i32) -> Biome {
+        let temperature = self.temperature_noise.fbm2(x as f32 * 0.02, z as f32 * 0.02, 2, 2.0, 0.5);
+        let moisture = self.moisture_noise.fbm2(x as f32 * 0.02, z as f32 * 0.02, 2, 2.0, 0.5);
+        if temperature > 0.2 && moisture < 0.0 {
+            Biome::Desert
+        } else if temperature < -0.2 {
+            Biome::Mountains
+        } else if moisture > 0.1 {
+            Biome::Forest
+        } else {
+            Biome::Plains
+        }
+    }
+
+    // Altura del terreno en la columna (x, z), en bloques
+    pub fn height_at(&self, x: i32, z: i32) -> i32 {
+        let (amplitude, base) = match self.biome_at(x, z) {
+            Biome::Mountains => (12.0, 10.0),
+            Biome::Desert => (2.0, 7.0),
+            Biome::Plains => (3.0, 8.0),
+            Biome::Forest => (4.0, 8.0),
+        };
+        let n = self.height_noise.fbm2(x as f32 * 0.05, z as f32 * 0.05, 4, 2.0, 0.5);
+        (base + n * amplitude).round() as i32
+    }
+
+    fn is_cave(&self, x: i32, y: i32, z: i32) -> bool {
+        let n = self.cave_noise.noise3(x as f32 * 0.12, y as f32 * 0.18, z as f32 * 0.12);
+        n.abs() < 0.06 && y > 1
+    }
+
+    // Vetas de mineral: el carbón es común, el hierro aparece solo en profundidad
+    fn ore_at(&self, x: i32, y: i32, z: i32, surface: i32) -> Option<Block> {
+        let n = self.ore_noise.noise3(x as f32 * 0.3, y as f32 * 0.3, z as f32 * 0.3);
+        if n > 0.55 && y < surface - 3 {
+            Some(Block::CoalOre)
+        } else if n < -0.6 && y < surface - 6 {
+            Some(Block::IronOre)
+        } else {
+            None
+        }
+    }
+
+    // Valor pseudoaleatorio reproducible en [0, 1) para la columna (x, z)
+    fn column_random(&self, x: i32, z: i32) -> f32 {
+        let mut h = self.seed ^ (x as u64).wrapping_mul(0x9E37_79B9_7F4A_7C15) ^ (z as u64).wrapping_mul(0xC2B2_AE3D_27D4_EB4F);
+        h ^= h >> 33;
+        h = h.wrapping_mul(0xFF51_AFD7_ED55_8CCD);
+        h ^= h >> 33;
+        (h >> 40) as f32 / (1u64 << 24) as f32
+    }
+
+    pub fn generate(&self, origin: Vec3, block_size: f32, size_x: usize, size_y: usize, size_z: usize, registry: &Rc<BlockRegistry>) -> BlockGrid {
+        let mut grid = BlockGrid::new(origin, block_size, size_x, size_y, size_z, registry);
+
+        for x in 0..size_x as i32 {
+            for z in 0..size_z as i32 {
+                let biome = self.biome_at(x, z);
+                let surface = self.height_at(x, z).clamp(1, size_y as i32 - 8);
+
+                for y in 0..=surface {
+                    let block = if y == surface {
+                        match biome {
+                            Biome::Desert => Block::Sand,
+                            Biome::Mountains if surface > 14 => Block::Snow,
+                            Biome::Mountains => Block::Stone,
+                            _ if surface <= self.sea_level => Block::Sand,
+                            _ => Block::Grass,
+                        }
+                    } else if y > surface - 3 {
+                        if biome == Biome::Desert { Block::Sand } else { Block::Dirt }
+                    } else {
+                        self.ore_at(x, y, z, surface).unwrap_or(Block::Stone)
+                    };
+                    if !self.is_cave(x, y, z) || y == 0 {
+                        grid.set(x, y, z, block);
+                    }
+                }
+
+                for y in surface + 1..=self.sea_level {
+                    grid.set(x, y, z, Block::Water);
+                }
+            }
+        }
+
+        self.place_trees(&mut grid);
+        grid
+    }
+
+    fn place_trees(&self, grid: &mut BlockGrid) {
+        let (size_x, size_y, size_z) = (grid.size_x as i32, grid.size_y as i32, grid.size_z as i32);
+        for x in 2..size_x - 2 {
+            for z in 2..size_z - 2 {
+                let density = match self.biome_at(x, z) {
+                    Biome::Forest => 0.06,
+                    Biome::Plains => 0.01,
+                    _ => 0.0,
+                };
+                if self.column_random(x, z) >= density {
+                    continue;
+                }
+
+                // Solo sobre pasto y sin otro tronco al lado
+                let surface = (0..size_y).rev().find(|&y| grid.get(x, y, z).is_solid()).unwrap_or(0);
+                if grid.get(x, surface, z) != Block::Grass || surface + 7 >= size_y {
+                    continue;
+                }
+                let crowded = (-1..=1).any(|dx| (-1..=1).any(|dz| grid.get(x + dx, surface + 1, z + dz) == Block::Wood));
+                if crowded {
+                    continue;
+                }
+
+                let trunk_height = 3 + (self.column_random(z, x) * 3.0) as i32;
+                for y in surface + 1..=surface + trunk_height {
+                    grid.set(x, y, z, Block::Wood);
+                }
+                let top = surface + trunk_height;
+                for dy in -1..=1 {
+                    let radius = if dy == 1 { 1 } else { 2 };
+                    for dx in -radius..=radius {
+                        for dz in -radius..=radius {
+                            if grid.get(x + dx, top + dy, z + dz) == Block::Air {
+                                grid.set(x + dx, top + dy, z + dz, Block::Leaves);
+                            }
+                        }
+                    }
+                }
+                grid.set(x, top + 2, z, Block::Leaves);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn generate(seed: u64) -> BlockGrid {
+        let registry = Rc::new(BlockRegistry::new(TextureAtlas::new(1, 1, 1)));
+        WorldGenerator::new(seed).generate(Vec3::zeros(), 1.0, 48, 24, 48, &registry)
+    }
+
+    // Bloques de madera que apoyan directamente en algo que no es madera: la base de cada tronco
+    fn trunk_bases(grid: &BlockGrid) -> Vec<(i32, i32, i32)> {
+        let mut bases = Vec::new();
+        for x in 0..grid.size_x as i32 {
+            for z in 0..grid.size_z as i32 {
+                for y in 1..grid.size_y as i32 {
+                    if grid.get(x, y, z) == Block::Wood && grid.get(x, y - 1, z) != Block::Wood {
+                        bases.push((x, y, z));
+                    }
+                }
+            }
+        }
+        bases
+    }
+
+    #[test]
+    fn same_seed_same_world() {
+        assert_eq!(generate(7).blocks, generate(7).blocks);
+    }
+
+    #[test]
+    fn different_seed_different_world() {
+        assert_ne!(generate(7).blocks, generate(8).blocks);
+    }
+
+    #[test]
+    fn trees_grow_on_grass_apart_from_each_other() {
+        let grid = generate(7);
+        let bases = trunk_bases(&grid);
+        assert!(!bases.is_empty(), "La semilla de prueba no genera árboles");
+        for (x, y, z) in bases {
+            assert_eq!(grid.get(x, y - 1, z), Block::Grass, "Tronco en ({}, {}, {}) fuera del pasto", x, y, z);
+            let height = (y..grid.size_y as i32).take_while(|&ty| grid.get(x, ty, z) == Block::Wood).count() as i32;
+            for (dx, dz) in [(-1, -1), (-1, 0), (-1, 1), (0, -1), (0, 1), (1, -1), (1, 0), (1, 1)] {
+                for ty in y..y + height {
+                    assert_ne!(grid.get(x + dx, ty, z + dz), Block::Wood, "Tronco en ({}, {}, {}) junto a otro", x, y, z);
+                }
+            }
+        }
+    }
+}